@@ -0,0 +1,161 @@
+//! Program error codes
+
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum EscrowError {
+    #[msg("Invalid escrow status for this operation")]
+    InvalidStatus,
+
+    #[msg("Unauthorized: Only agent or expired escrow can release")]
+    Unauthorized,
+
+    #[msg("Invalid quality score (must be 0-100)")]
+    InvalidQualityScore,
+
+    #[msg("Invalid refund percentage (must be 0-100)")]
+    InvalidRefundPercentage,
+
+    #[msg("Invalid verifier signature")]
+    InvalidSignature,
+
+    #[msg("Invalid time lock: must be between 1 hour and 30 days")]
+    InvalidTimeLock,
+
+    #[msg("Invalid amount: must be greater than 0")]
+    InvalidAmount,
+
+    #[msg("Invalid transaction ID: must be non-empty and max 64 chars")]
+    InvalidTransactionId,
+
+    #[msg("Time lock not expired: cannot release funds yet")]
+    TimeLockNotExpired,
+
+    #[msg("Dispute window expired: cannot dispute after time lock")]
+    DisputeWindowExpired,
+
+    #[msg("Amount too large: exceeds maximum escrow amount")]
+    AmountTooLarge,
+
+    #[msg("Insufficient funds to pay dispute cost")]
+    InsufficientDisputeFunds,
+
+    #[msg("Rate limit exceeded: too many transactions")]
+    RateLimitExceeded,
+
+    #[msg("Provider is suspended")]
+    ProviderSuspended,
+
+    #[msg("Reputation score too low for this operation")]
+    ReputationTooLow,
+
+    #[msg("Arithmetic overflow in calculation")]
+    ArithmeticOverflow,
+
+    #[msg("Insufficient rent reserve in escrow account")]
+    InsufficientRentReserve,
+
+    #[msg("Invalid Switchboard attestation")]
+    InvalidSwitchboardAttestation,
+
+    #[msg("Switchboard attestation is stale (older than 60 seconds)")]
+    StaleAttestation,
+
+    #[msg("Quality score mismatch between Switchboard and submitted value")]
+    QualityScoreMismatch,
+
+    #[msg("Invalid reputation threshold (must be 0-1000)")]
+    InvalidReputationThreshold,
+
+    #[msg("Account is already at the current layout version")]
+    AccountAlreadyMigrated,
+
+    #[msg("Dispute rate limit exceeded: too many disputes today")]
+    DisputeRateLimitExceeded,
+
+    #[msg("Insufficient stake for this operation")]
+    InsufficientStake,
+
+    #[msg("Unbonding period has not elapsed")]
+    UnbondingNotComplete,
+
+    #[msg("Invalid verification level for this operation")]
+    InvalidVerificationLevel,
+
+    #[msg("Invalid attestation expiry: must be in the future and within 365 days")]
+    InvalidAttestationExpiry,
+
+    #[msg("Provider rate limit exceeded for this agent")]
+    ProviderRateLimitExceeded,
+
+    #[msg("Invalid dispute pricing: tiers must ascend to 100 with multipliers of at least 1")]
+    InvalidDisputePricing,
+
+    #[msg("Invalid penalty policy: thresholds and duration must be non-zero")]
+    InvalidPenaltyPolicy,
+
+    #[msg("Provider is not suspended")]
+    ProviderNotSuspended,
+
+    #[msg("Invalid work agreement: query must be 1-128 bytes")]
+    InvalidWorkAgreement,
+
+    #[msg("Delivery has already been confirmed for this escrow")]
+    DeliveryAlreadyConfirmed,
+
+    #[msg("Invalid provider terms")]
+    InvalidProviderTerms,
+
+    #[msg("Escrow does not meet the provider's published terms")]
+    ProviderTermsNotMet,
+
+    #[msg("Verifier is not accepted by this provider")]
+    VerifierNotAccepted,
+
+    #[msg("Invalid or missing SLA terms")]
+    InvalidSlaTerms,
+
+    #[msg("Provider bond does not cover this escrow")]
+    InsufficientProviderBond,
+
+    #[msg("Dispute window must be 0 or between 10 minutes and 30 days")]
+    InvalidDisputeWindow,
+
+    #[msg("Resolution deadline has not been reached")]
+    ResolutionDeadlineNotReached,
+
+    #[msg("Resolution period must be between 1 hour and 30 days")]
+    InvalidResolutionPolicy,
+
+    #[msg("Escrow memo must be at most 64 bytes")]
+    InvalidEscrowMetadata,
+
+    #[msg("Invalid session key terms")]
+    InvalidSessionKey,
+
+    #[msg("Session key has expired")]
+    SessionKeyExpired,
+
+    #[msg("Session key spending limit exceeded")]
+    SessionLimitExceeded,
+
+    #[msg("Agent vault balance too low")]
+    InsufficientVaultBalance,
+
+    #[msg("Account must be migrated to the current layout version first")]
+    AccountNotMigrated,
+
+    #[msg("Escrows with a work agreement must be resolved by a verifier signature")]
+    AgreementRequiresVerifier,
+
+    #[msg("Delivery can no longer be confirmed for this escrow")]
+    DeliveryConfirmationTooLate,
+
+    #[msg("Session keys can only spend from the agent's vault")]
+    SessionRequiresVault,
+
+    #[msg("Verifier list is too long or contains the default key")]
+    InvalidVerifierList,
+}
+
+
@@ -0,0 +1,293 @@
+//! Events emitted by the program
+
+use anchor_lang::prelude::*;
+
+use crate::*;
+
+#[event]
+pub struct EscrowInitialized {
+    pub escrow: Pubkey,
+    pub agent: Pubkey,
+    pub api: Pubkey,
+    pub amount: u64,
+    pub expires_at: i64,
+    pub transaction_id: String,
+    pub metadata: Option<EscrowMetadata>,
+    pub funded_from_vault: bool,
+}
+
+#[event]
+pub struct WorkAgreementCreated {
+    pub escrow: Pubkey,
+    pub agreement: Pubkey,
+    pub agreement_hash: [u8; 32],
+}
+
+#[event]
+pub struct DeliveryConfirmed {
+    pub escrow: Pubkey,
+    pub api: Pubkey,
+    pub response_hash: [u8; 32],
+    pub record_count: u32,
+    pub delivered_at: i64,
+    pub dispute_window_end: i64,
+}
+
+#[event]
+pub struct EscrowSlaSet {
+    pub escrow: Pubkey,
+    pub max_latency_ms: u32,
+    pub deadline: i64,
+}
+
+#[event]
+pub struct SlaSettled {
+    pub escrow: Pubkey,
+    pub transaction_id: String,
+    pub latency_ms: u32,
+    pub availability_bps: u16,
+    pub deadline_missed: bool,
+    pub refund_percentage: u8,
+    pub refund_amount: u64,
+    pub payment_amount: u64,
+    pub verifier: Pubkey,
+    pub bond_returned: u64,
+    pub bond_forfeited: u64,
+}
+
+#[event]
+pub struct DisputeMarked {
+    pub escrow: Pubkey,
+    pub agent: Pubkey,
+    pub transaction_id: String,
+    pub timestamp: i64,
+    pub bond_amount: u64,
+    pub resolution_deadline: i64,
+}
+
+#[event]
+pub struct UnresolvedSettled {
+    pub escrow: Pubkey,
+    pub transaction_id: String,
+    pub refund_percentage: u8,
+    pub refund_amount: u64,
+    pub payment_amount: u64,
+    pub bond_returned: u64,
+    pub bond_forfeited: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UnresolvedPolicyUpdated {
+    pub resolution_period: i64,
+    pub default_refund_percentage: u8,
+}
+
+#[event]
+pub struct StakedLevelThresholdUpdated {
+    pub staked_level_threshold: u64,
+}
+
+#[event]
+pub struct DefaultVerifiersUpdated {
+    pub verifiers: Vec<Pubkey>,
+}
+
+#[event]
+pub struct DisputeRateLimitConsumed {
+    pub entity: Pubkey,
+    pub disputes_remaining: u16,
+    pub dispute_day_limit: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DisputeResolved {
+    pub escrow: Pubkey,
+    pub transaction_id: String,
+    pub quality_score: u8,
+    pub refund_percentage: u8,
+    pub refund_amount: u64,
+    pub payment_amount: u64,
+    pub verifier: Pubkey,
+    pub bond_returned: u64,
+    pub bond_forfeited: u64,
+}
+
+#[event]
+pub struct AccountMigrated {
+    pub account: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+}
+
+#[event]
+pub struct StakeUpdated {
+    pub entity: Pubkey,
+    pub staked_amount: u64,
+    pub unbonding_amount: u64,
+    pub unbonding_ends_at: i64,
+    pub verification_level: VerificationLevel,
+}
+
+#[event]
+pub struct VerificationLevelChanged {
+    pub entity: Pubkey,
+    pub old_level: VerificationLevel,
+    pub new_level: VerificationLevel,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VerificationAttested {
+    pub entity: Pubkey,
+    pub attestor: Pubkey,
+    pub level: VerificationLevel,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct VerificationRevoked {
+    pub entity: Pubkey,
+    pub attestor: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProviderLimitsUpdated {
+    pub api: Pubkey,
+    pub max_escrows_per_hour: u16,
+    pub max_open_escrows: u32,
+    pub max_locked_value: u64,
+}
+
+#[event]
+pub struct PenaltyPolicyUpdated {
+    pub poor_quality_per_strike: u32,
+    pub strikes_to_suspend: u8,
+    pub suspension_duration: i64,
+}
+
+#[event]
+pub struct ProviderStrikeAdded {
+    pub provider: Pubkey,
+    pub strike_count: u8,
+    pub poor_quality_count: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProviderSuspended {
+    pub provider: Pubkey,
+    pub suspension_end: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StrikesDecayed {
+    pub provider: Pubkey,
+    pub strikes_removed: u8,
+    pub strike_count: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AppealPolicyUpdated {
+    pub appeal_bond: u64,
+    pub strike_decay_period: i64,
+    pub treasury: Pubkey,
+}
+
+#[event]
+pub struct AppealFiled {
+    pub provider: Pubkey,
+    pub evidence_hash: [u8; 32],
+    pub bond: u64,
+    pub suspension_end: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AppealResolved {
+    pub provider: Pubkey,
+    pub approved: bool,
+    pub bond_returned: u64,
+    pub bond_forfeited: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProviderReinstated {
+    pub provider: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProviderTermsUpdated {
+    pub api: Pubkey,
+    pub payout: Pubkey,
+    pub endpoint_hash: [u8; 32],
+    pub default_time_lock: i64,
+    pub default_price: u64,
+    pub accepted_verifiers: Vec<Pubkey>,
+    pub min_agent_reputation: u16,
+}
+
+#[event]
+pub struct ProviderBondUpdated {
+    pub api: Pubkey,
+    pub balance: u64,
+    pub reserved: u64,
+}
+
+#[event]
+pub struct BondPenaltyPaid {
+    pub escrow: Pubkey,
+    pub api: Pubkey,
+    pub agent: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct ProviderDisputeWindowUpdated {
+    pub api: Pubkey,
+    pub dispute_window: i64,
+}
+
+#[event]
+pub struct AgentVaultUpdated {
+    pub owner: Pubkey,
+    pub available: u64,
+}
+
+#[event]
+pub struct SessionKeyCreated {
+    pub owner: Pubkey,
+    pub session_key: Pubkey,
+    pub expires_at: i64,
+    pub max_per_escrow: u64,
+    pub spend_cap: u64,
+    pub allowed_providers: Vec<Pubkey>,
+}
+
+#[event]
+pub struct SessionKeyRevoked {
+    pub owner: Pubkey,
+    pub session_key: Pubkey,
+    pub spent: u64,
+}
+
+#[event]
+pub struct DisputePricingUpdated {
+    pub base_dispute_cost: u64,
+    pub tiers: [DisputeCostTier; DISPUTE_COST_TIERS],
+}
+
+#[event]
+pub struct FundsReleased {
+    pub escrow: Pubkey,
+    pub transaction_id: String,
+    pub amount: u64,
+    pub api: Pubkey,
+    pub timestamp: i64,
+}
@@ -0,0 +1,199 @@
+//! Program-wide policy controlled by the config authority
+
+use anchor_lang::prelude::*;
+
+use crate::*;
+
+#[derive(Accounts)]
+pub struct InitConfig<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ProgramConfig::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::X402Escrow>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ EscrowError::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = authority @ EscrowError::Unauthorized,
+        constraint = config.version == ProgramConfig::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    pub authority: Signer<'info>,
+}
+
+pub(crate) fn init_config(ctx: Context<InitConfig>) -> Result<()> {
+    let authority = ctx.accounts.authority.key();
+
+    ctx.accounts.config.set_inner(ProgramConfig::new(authority, ctx.bumps.config));
+
+    msg!("Config initialized with authority {}", authority);
+
+    Ok(())
+}
+
+
+pub(crate) fn set_config_authority(ctx: Context<UpdateConfig>, new_authority: Pubkey) -> Result<()> {
+    ctx.accounts.config.authority = new_authority;
+
+    msg!("Config authority set to {}", new_authority);
+
+    Ok(())
+}
+
+
+pub(crate) fn set_dispute_pricing(
+    ctx: Context<UpdateConfig>,
+    base_dispute_cost: u64,
+    tiers: [DisputeCostTier; DISPUTE_COST_TIERS],
+) -> Result<()> {
+    require!(base_dispute_cost > 0, EscrowError::InvalidDisputePricing);
+    require!(
+        tiers.windows(2).all(|pair| pair[0].max_rate < pair[1].max_rate)
+            && tiers[DISPUTE_COST_TIERS - 1].max_rate >= 100
+            && tiers.iter().all(|tier| tier.multiplier >= 1),
+        EscrowError::InvalidDisputePricing
+    );
+
+    let config = &mut ctx.accounts.config;
+    config.base_dispute_cost = base_dispute_cost;
+    config.dispute_cost_tiers = tiers;
+
+    emit!(DisputePricingUpdated {
+        base_dispute_cost,
+        tiers,
+    });
+
+    Ok(())
+}
+
+
+pub(crate) fn set_penalty_policy(
+    ctx: Context<UpdateConfig>,
+    poor_quality_per_strike: u32,
+    strikes_to_suspend: u8,
+    suspension_duration: i64,
+) -> Result<()> {
+    require!(
+        poor_quality_per_strike > 0 && strikes_to_suspend > 0 && suspension_duration > 0,
+        EscrowError::InvalidPenaltyPolicy
+    );
+
+    let config = &mut ctx.accounts.config;
+    config.poor_quality_per_strike = poor_quality_per_strike;
+    config.strikes_to_suspend = strikes_to_suspend;
+    config.suspension_duration = suspension_duration;
+
+    emit!(PenaltyPolicyUpdated {
+        poor_quality_per_strike,
+        strikes_to_suspend,
+        suspension_duration,
+    });
+
+    Ok(())
+}
+
+
+pub(crate) fn set_appeal_policy(
+    ctx: Context<UpdateConfig>,
+    appeal_bond: u64,
+    strike_decay_period: i64,
+    treasury: Pubkey,
+) -> Result<()> {
+    require!(strike_decay_period > 0, EscrowError::InvalidPenaltyPolicy);
+
+    let config = &mut ctx.accounts.config;
+    config.appeal_bond = appeal_bond;
+    config.strike_decay_period = strike_decay_period;
+    config.treasury = treasury;
+
+    emit!(AppealPolicyUpdated {
+        appeal_bond,
+        strike_decay_period,
+        treasury,
+    });
+
+    Ok(())
+}
+
+
+pub(crate) fn set_unresolved_policy(
+    ctx: Context<UpdateConfig>,
+    resolution_period: i64,
+    default_refund_percentage: u8,
+) -> Result<()> {
+    require!(
+        (RATE_LIMIT_HOUR..=MAX_TIME_LOCK).contains(&resolution_period),
+        EscrowError::InvalidResolutionPolicy
+    );
+    require!(default_refund_percentage <= 100, EscrowError::InvalidRefundPercentage);
+
+    let config = &mut ctx.accounts.config;
+    config.resolution_period = resolution_period;
+    config.default_refund_percentage = default_refund_percentage;
+
+    emit!(UnresolvedPolicyUpdated {
+        resolution_period,
+        default_refund_percentage,
+    });
+
+    Ok(())
+}
+
+
+pub(crate) fn set_staked_level_threshold(
+    ctx: Context<UpdateConfig>,
+    staked_level_threshold: u64,
+) -> Result<()> {
+    require!(staked_level_threshold > 0, EscrowError::InvalidAmount);
+
+    ctx.accounts.config.staked_level_threshold = staked_level_threshold;
+
+    emit!(StakedLevelThresholdUpdated {
+        staked_level_threshold,
+    });
+
+    Ok(())
+}
+
+
+pub(crate) fn set_default_verifiers(
+    ctx: Context<UpdateConfig>,
+    verifiers: Vec<Pubkey>,
+) -> Result<()> {
+    require!(
+        verifiers.len() <= MAX_ACCEPTED_VERIFIERS
+            && !verifiers.contains(&Pubkey::default()),
+        EscrowError::InvalidVerifierList
+    );
+
+    let mut verifier_slots = [Pubkey::default(); MAX_ACCEPTED_VERIFIERS];
+    verifier_slots[..verifiers.len()].copy_from_slice(&verifiers);
+    ctx.accounts.config.default_verifiers = verifier_slots;
+
+    emit!(DefaultVerifiersUpdated { verifiers });
+
+    Ok(())
+}
@@ -0,0 +1,887 @@
+//! Dispute resolution: verifier signatures, Switchboard feeds, SLA
+//! measurements and the unresolved fallback
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions::ID as INSTRUCTIONS_ID;
+use switchboard_on_demand::on_demand::accounts::pull_feed::PullFeedAccountData;
+
+use crate::*;
+
+#[derive(Accounts)]
+pub struct SettleSla<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.transaction_id.as_bytes()],
+        bump = escrow.bump,
+        has_one = agent @ EscrowError::Unauthorized,
+        has_one = api @ EscrowError::Unauthorized,
+        has_one = payout @ EscrowError::Unauthorized,
+        constraint = escrow.version == Escrow::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(mut)]
+    pub agent: SystemAccount<'info>,
+
+    /// CHECK: API wallet address, checked against the escrow
+    pub api: AccountInfo<'info>,
+
+    /// CHECK: Receives the payment share, checked against the escrow
+    #[account(mut)]
+    pub payout: AccountInfo<'info>,
+
+    /// CHECK: Agent vault PDA; receives refunds for escrows funded from it
+    #[account(mut, seeds = [b"vault", escrow.agent.as_ref()], bump)]
+    pub agent_vault: AccountInfo<'info>,
+
+    /// CHECK: Provider's bond PDA; only read when the escrow reserved from it
+    #[account(mut, seeds = [b"bond", escrow.api.as_ref()], bump)]
+    pub provider_bond: AccountInfo<'info>,
+
+    /// CHECK: Verifier public key
+    pub verifier: AccountInfo<'info>,
+
+    /// CHECK: Instructions sysvar for Ed25519 signature verification
+    #[account(address = INSTRUCTIONS_ID)]
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"relationship", agent.key().as_ref(), api.key().as_ref()],
+        bump = relationship.bump,
+        constraint = relationship.version == Relationship::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub relationship: Account<'info, Relationship>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SettleUnresolved<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.transaction_id.as_bytes()],
+        bump = escrow.bump,
+        has_one = agent @ EscrowError::Unauthorized,
+        has_one = payout @ EscrowError::Unauthorized,
+        constraint = escrow.version == Escrow::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(mut)]
+    pub agent: SystemAccount<'info>,
+
+    /// CHECK: Receives the payment share, checked against the escrow
+    #[account(mut)]
+    pub payout: AccountInfo<'info>,
+
+    /// CHECK: Agent vault PDA; receives refunds for escrows funded from it
+    #[account(mut, seeds = [b"vault", escrow.agent.as_ref()], bump)]
+    pub agent_vault: AccountInfo<'info>,
+
+    /// CHECK: Provider's bond PDA; only read when the escrow reserved from it
+    #[account(mut, seeds = [b"bond", escrow.api.as_ref()], bump)]
+    pub provider_bond: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"relationship", escrow.agent.as_ref(), escrow.api.as_ref()],
+        bump = relationship.bump,
+        constraint = relationship.version == Relationship::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub relationship: Account<'info, Relationship>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveDispute<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.transaction_id.as_bytes()],
+        bump = escrow.bump,
+        has_one = agent @ EscrowError::Unauthorized,
+        has_one = api @ EscrowError::Unauthorized,
+        constraint = escrow.version == Escrow::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(mut)]
+    pub agent: SystemAccount<'info>,
+
+    /// CHECK: API wallet address, checked against the escrow
+    #[account(mut)]
+    pub api: AccountInfo<'info>,
+
+    /// CHECK: Receives the payment share; fixed at escrow creation
+    #[account(mut, address = escrow.payout @ EscrowError::Unauthorized)]
+    pub payout: AccountInfo<'info>,
+
+    /// CHECK: Agent vault PDA; receives refunds for escrows funded from it
+    #[account(mut, seeds = [b"vault", escrow.agent.as_ref()], bump)]
+    pub agent_vault: AccountInfo<'info>,
+
+    /// CHECK: Provider's bond PDA; only read when the escrow reserved from it
+    #[account(mut, seeds = [b"bond", escrow.api.as_ref()], bump)]
+    pub provider_bond: AccountInfo<'info>,
+
+    /// CHECK: Verifier oracle public key
+    pub verifier: AccountInfo<'info>,
+
+    /// CHECK: Instructions sysvar for Ed25519 signature verification
+    #[account(address = INSTRUCTIONS_ID)]
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"reputation", agent.key().as_ref()],
+        bump = agent_reputation.bump,
+        constraint = agent_reputation.version == EntityReputation::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub agent_reputation: Account<'info, EntityReputation>,
+
+    #[account(
+        mut,
+        seeds = [b"reputation", api.key().as_ref()],
+        bump = api_reputation.bump,
+        constraint = api_reputation.version == EntityReputation::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub api_reputation: Account<'info, EntityReputation>,
+
+    #[account(
+        mut,
+        seeds = [b"relationship", agent.key().as_ref(), api.key().as_ref()],
+        bump = relationship.bump,
+        constraint = relationship.version == Relationship::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub relationship: Account<'info, Relationship>,
+
+    /// Created on the provider's first resolved dispute
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ProviderPenalties::INIT_SPACE,
+        seeds = [b"penalties", api.key().as_ref()],
+        bump
+    )]
+    pub provider_penalties: Account<'info, ProviderPenalties>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// Relayer; pays rent if the provider's penalty account is created
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveDisputeSwitchboard<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.transaction_id.as_bytes()],
+        bump = escrow.bump,
+        has_one = agent @ EscrowError::Unauthorized,
+        has_one = api @ EscrowError::Unauthorized,
+        constraint = escrow.version == Escrow::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(mut)]
+    pub agent: SystemAccount<'info>,
+
+    /// CHECK: API wallet address, checked against the escrow
+    #[account(mut)]
+    pub api: AccountInfo<'info>,
+
+    /// CHECK: Receives the payment share; fixed at escrow creation
+    #[account(mut, address = escrow.payout @ EscrowError::Unauthorized)]
+    pub payout: AccountInfo<'info>,
+
+    /// CHECK: Agent vault PDA; receives refunds for escrows funded from it
+    #[account(mut, seeds = [b"vault", escrow.agent.as_ref()], bump)]
+    pub agent_vault: AccountInfo<'info>,
+
+    /// CHECK: Provider's bond PDA; only read when the escrow reserved from it
+    #[account(mut, seeds = [b"bond", escrow.api.as_ref()], bump)]
+    pub provider_bond: AccountInfo<'info>,
+
+    /// Switchboard Function pull feed containing quality score
+    /// CHECK: Validated via PullFeedAccountData::parse
+    pub switchboard_function: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"reputation", agent.key().as_ref()],
+        bump = agent_reputation.bump,
+        constraint = agent_reputation.version == EntityReputation::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub agent_reputation: Account<'info, EntityReputation>,
+
+    #[account(
+        mut,
+        seeds = [b"reputation", api.key().as_ref()],
+        bump = api_reputation.bump,
+        constraint = api_reputation.version == EntityReputation::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub api_reputation: Account<'info, EntityReputation>,
+
+    #[account(
+        mut,
+        seeds = [b"relationship", agent.key().as_ref(), api.key().as_ref()],
+        bump = relationship.bump,
+        constraint = relationship.version == Relationship::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub relationship: Account<'info, Relationship>,
+
+    /// Created on the provider's first resolved dispute
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ProviderPenalties::INIT_SPACE,
+        seeds = [b"penalties", api.key().as_ref()],
+        bump
+    )]
+    pub provider_penalties: Account<'info, ProviderPenalties>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// Relayer; pays rent if the provider's penalty account is created
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub(crate) fn resolve_dispute(
+    ctx: Context<ResolveDispute>,
+    quality_score: u8,
+    refund_percentage: u8,
+    signature: [u8; 64],
+) -> Result<()> {
+    let escrow = &mut ctx.accounts.escrow;
+
+    require!(
+        escrow.status == EscrowStatus::Active || escrow.status == EscrowStatus::Disputed,
+        EscrowError::InvalidStatus
+    );
+
+    require!(quality_score <= 100, EscrowError::InvalidQualityScore);
+    require!(
+        refund_percentage <= MAX_PENALTY_REFUND_PERCENTAGE,
+        EscrowError::InvalidRefundPercentage
+    );
+    // Refunds above 100% are paid from the reserved provider bond
+    require!(
+        refund_percentage <= 100 || escrow.bond_reserved > 0,
+        EscrowError::InsufficientProviderBond
+    );
+
+    // Verify signature from verifier oracle (see `verifier_message`)
+    let message = verifier_message(escrow, quality_score, refund_percentage);
+    let message_bytes = message.as_bytes();
+
+    // Verify Ed25519 signature from the instructions sysvar
+    verify_ed25519_signature(
+        &ctx.accounts.instructions_sysvar,
+        &signature,
+        ctx.accounts.verifier.key,
+        message_bytes,
+    )?;
+    require_accepted_verifier(escrow, &ctx.accounts.config, ctx.accounts.verifier.key)?;
+
+    msg!("Verifier: {}", ctx.accounts.verifier.key());
+    msg!("Quality Score: {}", quality_score);
+    msg!("Refund: {}%", refund_percentage);
+
+    // Anything above 100% is the bond penalty
+    let penalty_amount = (escrow.amount as u128)
+        .checked_mul(refund_percentage.saturating_sub(100) as u128)
+        .ok_or(EscrowError::ArithmeticOverflow)?
+        .checked_div(100)
+        .ok_or(EscrowError::ArithmeticOverflow)? as u64;
+
+    // Refund the agent (or the vault that funded the escrow) and pay the API
+    let refund_to = refund_account(
+        &ctx.accounts.escrow,
+        ctx.accounts.agent.to_account_info(),
+        ctx.accounts.agent_vault.clone(),
+    );
+    let (refund_amount, payment_amount, bond_returned, bond_forfeited) = distribute_settlement(
+        &ctx.accounts.escrow.to_account_info(),
+        &ctx.accounts.escrow,
+        &refund_to,
+        &ctx.accounts.payout.to_account_info(),
+        refund_percentage.min(100),
+    )?;
+
+    msg!("Refund to Agent: {} SOL", refund_amount as f64 / 1_000_000_000.0);
+    msg!("Payment to API: {} SOL", payment_amount as f64 / 1_000_000_000.0);
+
+    settle_provider_bond(
+        &ctx.accounts.provider_bond,
+        &refund_to,
+        &ctx.accounts.escrow,
+        penalty_amount,
+    )?;
+
+    let escrow = &mut ctx.accounts.escrow;
+    escrow.status = EscrowStatus::Resolved;
+    escrow.quality_score = Some(quality_score);
+    escrow.refund_percentage = Some(refund_percentage);
+
+    // Update agent reputation
+    let agent_reputation = &mut ctx.accounts.agent_reputation;
+    let clock = Clock::get()?;
+
+    record_relationship_settlement(
+        &mut ctx.accounts.relationship,
+        escrow.amount,
+        Some(quality_score),
+        clock.unix_timestamp,
+    );
+
+    agent_reputation.total_transactions = agent_reputation.total_transactions.saturating_add(1);
+
+    // Record quality received by agent
+    record_quality_sample(agent_reputation, quality_score);
+
+    // Categorize dispute outcome for agent
+    if refund_percentage >= 75 {
+        agent_reputation.disputes_won = agent_reputation.disputes_won.saturating_add(1);
+    } else if refund_percentage >= 25 {
+        agent_reputation.disputes_partial = agent_reputation.disputes_partial.saturating_add(1);
+    } else {
+        agent_reputation.disputes_lost = agent_reputation.disputes_lost.saturating_add(1);
+    }
+
+    // Recalculate agent reputation score
+    agent_reputation.reputation_score = calculate_reputation_score(agent_reputation);
+    agent_reputation.last_updated = clock.unix_timestamp;
+
+    // Update API reputation (inverse of agent outcome)
+    let api_reputation = &mut ctx.accounts.api_reputation;
+    api_reputation.total_transactions = api_reputation.total_transactions.saturating_add(1);
+
+    // Quality delivered by API (inverse of refund percentage)
+    let quality_delivered = 100u8.saturating_sub(refund_percentage);
+    record_quality_sample(api_reputation, quality_delivered);
+
+    // Categorize for API (inverse)
+    if refund_percentage <= 25 {
+        // API provided good quality
+        api_reputation.disputes_won = api_reputation.disputes_won.saturating_add(1);
+    } else if refund_percentage <= 75 {
+        api_reputation.disputes_partial = api_reputation.disputes_partial.saturating_add(1);
+    } else {
+        // API provided poor quality
+        api_reputation.disputes_lost = api_reputation.disputes_lost.saturating_add(1);
+    }
+
+    api_reputation.reputation_score = calculate_reputation_score(api_reputation);
+    api_reputation.last_updated = clock.unix_timestamp;
+
+    prepare_provider_penalties(
+        &mut ctx.accounts.provider_penalties,
+        ctx.accounts.api.key(),
+        ctx.bumps.provider_penalties,
+        clock.unix_timestamp,
+    )?;
+    record_provider_outcome(
+        &mut ctx.accounts.provider_penalties,
+        &ctx.accounts.config,
+        refund_amount.saturating_add(penalty_amount),
+        quality_score,
+        clock.unix_timestamp,
+    );
+
+    if penalty_amount > 0 {
+        emit!(BondPenaltyPaid {
+            escrow: escrow.key(),
+            api: escrow.api,
+            agent: escrow.agent,
+            amount: penalty_amount,
+        });
+    }
+
+    msg!("Dispute resolved!");
+    msg!("Agent reputation: {}", agent_reputation.reputation_score);
+    msg!("API reputation: {}", api_reputation.reputation_score);
+
+    emit!(DisputeResolved {
+        escrow: escrow.key(),
+        transaction_id: escrow.transaction_id.clone(),
+        quality_score,
+        refund_percentage,
+        refund_amount,
+        payment_amount,
+        verifier: ctx.accounts.verifier.key(),
+        bond_returned,
+        bond_forfeited,
+    });
+
+    Ok(())
+}
+
+
+pub(crate) fn resolve_dispute_switchboard(
+    ctx: Context<ResolveDisputeSwitchboard>,
+    quality_score: u8,
+) -> Result<()> {
+    let escrow = &mut ctx.accounts.escrow;
+
+    require!(
+        escrow.status == EscrowStatus::Active || escrow.status == EscrowStatus::Disputed,
+        EscrowError::InvalidStatus
+    );
+
+    require!(
+        escrow.agreement_hash.is_none(),
+        EscrowError::AgreementRequiresVerifier
+    );
+    require!(quality_score <= 100, EscrowError::InvalidQualityScore);
+    let refund_percentage = 100 - quality_score;
+
+    // Verify Switchboard attestation
+    // The Switchboard Function result is stored in pull_feed account
+    // and contains the quality score signed by oracle nodes
+    let pull_feed = &ctx.accounts.switchboard_function;
+
+    // Load and verify the Switchboard attestation
+    let feed_account_info = pull_feed.to_account_info();
+    let feed_data = PullFeedAccountData::parse(feed_account_info.data.borrow())
+        .map_err(|_| EscrowError::InvalidSwitchboardAttestation)?;
+    require_accepted_verifier(escrow, &ctx.accounts.config, pull_feed.key)?;
+
+    // Validate timestamp freshness (attestation must be within 300 seconds)
+    let clock = Clock::get()?;
+    let age_seconds = clock.unix_timestamp - feed_data.last_update_timestamp;
+
+    require!(
+        age_seconds >= 0 && age_seconds <= 300,
+        EscrowError::StaleAttestation
+    );
+
+    msg!("Switchboard attestation age: {} seconds", age_seconds);
+
+    // Extract quality score from Switchboard result
+    // The value is encoded as i128 in the feed
+    let switchboard_quality = feed_data.result.value;
+
+    // Verify the quality score matches what was submitted
+    require!(
+        switchboard_quality == quality_score as i128,
+        EscrowError::QualityScoreMismatch
+    );
+
+    msg!("Switchboard Quality Score: {}", quality_score);
+    msg!("Refund: {}%", refund_percentage);
+
+    // Same split as resolve_dispute
+    let refund_to = refund_account(
+        &ctx.accounts.escrow,
+        ctx.accounts.agent.to_account_info(),
+        ctx.accounts.agent_vault.clone(),
+    );
+    let (refund_amount, payment_amount, bond_returned, bond_forfeited) = distribute_settlement(
+        &ctx.accounts.escrow.to_account_info(),
+        &ctx.accounts.escrow,
+        &refund_to,
+        &ctx.accounts.payout.to_account_info(),
+        refund_percentage,
+    )?;
+
+    msg!("Refund to Agent: {} SOL", refund_amount as f64 / 1_000_000_000.0);
+    msg!("Payment to API: {} SOL", payment_amount as f64 / 1_000_000_000.0);
+
+    settle_provider_bond(&ctx.accounts.provider_bond, &refund_to, &ctx.accounts.escrow, 0)?;
+
+    let escrow = &mut ctx.accounts.escrow;
+    escrow.status = EscrowStatus::Resolved;
+    escrow.quality_score = Some(quality_score);
+    escrow.refund_percentage = Some(refund_percentage);
+
+    // Update agent reputation (same logic as resolve_dispute)
+    let agent_reputation = &mut ctx.accounts.agent_reputation;
+    let clock = Clock::get()?;
+
+    record_relationship_settlement(
+        &mut ctx.accounts.relationship,
+        escrow.amount,
+        Some(quality_score),
+        clock.unix_timestamp,
+    );
+
+    agent_reputation.total_transactions = agent_reputation.total_transactions.saturating_add(1);
+
+    record_quality_sample(agent_reputation, quality_score);
+
+    if refund_percentage >= 75 {
+        agent_reputation.disputes_won = agent_reputation.disputes_won.saturating_add(1);
+    } else if refund_percentage >= 25 {
+        agent_reputation.disputes_partial = agent_reputation.disputes_partial.saturating_add(1);
+    } else {
+        agent_reputation.disputes_lost = agent_reputation.disputes_lost.saturating_add(1);
+    }
+
+    agent_reputation.reputation_score = calculate_reputation_score(agent_reputation);
+    agent_reputation.last_updated = clock.unix_timestamp;
+
+    // Update API reputation
+    let api_reputation = &mut ctx.accounts.api_reputation;
+    api_reputation.total_transactions = api_reputation.total_transactions.saturating_add(1);
+
+    let quality_delivered = 100 - refund_percentage;
+    record_quality_sample(api_reputation, quality_delivered);
+
+    if refund_percentage <= 25 {
+        api_reputation.disputes_won = api_reputation.disputes_won.saturating_add(1);
+    } else if refund_percentage <= 75 {
+        api_reputation.disputes_partial = api_reputation.disputes_partial.saturating_add(1);
+    } else {
+        api_reputation.disputes_lost = api_reputation.disputes_lost.saturating_add(1);
+    }
+
+    api_reputation.reputation_score = calculate_reputation_score(api_reputation);
+    api_reputation.last_updated = clock.unix_timestamp;
+
+    prepare_provider_penalties(
+        &mut ctx.accounts.provider_penalties,
+        ctx.accounts.api.key(),
+        ctx.bumps.provider_penalties,
+        clock.unix_timestamp,
+    )?;
+    record_provider_outcome(
+        &mut ctx.accounts.provider_penalties,
+        &ctx.accounts.config,
+        refund_amount,
+        quality_score,
+        clock.unix_timestamp,
+    );
+
+    msg!("Dispute resolved via Switchboard!");
+    msg!("Agent reputation: {}", agent_reputation.reputation_score);
+    msg!("API reputation: {}", api_reputation.reputation_score);
+
+    emit!(DisputeResolved {
+        escrow: escrow.key(),
+        transaction_id: escrow.transaction_id.clone(),
+        quality_score,
+        refund_percentage,
+        refund_amount,
+        payment_amount,
+        verifier: ctx.accounts.switchboard_function.key(),
+        bond_returned,
+        bond_forfeited,
+    });
+
+    Ok(())
+}
+
+
+pub(crate) fn settle_sla(
+    ctx: Context<SettleSla>,
+    latency_ms: u32,
+    availability_bps: u16,
+    signature: [u8; 64],
+) -> Result<()> {
+    let escrow = &ctx.accounts.escrow;
+    let clock = Clock::get()?;
+
+    require!(
+        escrow.status == EscrowStatus::Active || escrow.status == EscrowStatus::Disputed,
+        EscrowError::InvalidStatus
+    );
+    require!(
+        escrow.sla_max_latency_ms > 0 || escrow.sla_deadline > 0,
+        EscrowError::InvalidSlaTerms
+    );
+    require!(availability_bps <= 10_000, EscrowError::InvalidSlaTerms);
+
+    let message = sla_message(escrow, latency_ms, availability_bps);
+    verify_ed25519_signature(
+        &ctx.accounts.instructions_sysvar,
+        &signature,
+        ctx.accounts.verifier.key,
+        message.as_bytes(),
+    )?;
+    require_accepted_verifier(
+        &ctx.accounts.escrow,
+        &ctx.accounts.config,
+        ctx.accounts.verifier.key,
+    )?;
+
+    // Undelivered past the deadline, or delivered after it
+    let deadline_missed = escrow.sla_deadline > 0
+        && if escrow.delivered_at == 0 {
+            clock.unix_timestamp >= escrow.sla_deadline
+        } else {
+            escrow.delivered_at > escrow.sla_deadline
+        };
+
+    let refund_percentage = if deadline_missed {
+        100
+    } else {
+        sla_refund_percentage(escrow.sla_max_latency_ms, latency_ms, availability_bps)
+    };
+
+    let refund_to = refund_account(
+        &ctx.accounts.escrow,
+        ctx.accounts.agent.to_account_info(),
+        ctx.accounts.agent_vault.clone(),
+    );
+    let (refund_amount, payment_amount, bond_returned, bond_forfeited) = distribute_settlement(
+        &ctx.accounts.escrow.to_account_info(),
+        &ctx.accounts.escrow,
+        &refund_to,
+        &ctx.accounts.payout.to_account_info(),
+        refund_percentage,
+    )?;
+    settle_provider_bond(&ctx.accounts.provider_bond, &refund_to, &ctx.accounts.escrow, 0)?;
+
+    let escrow = &mut ctx.accounts.escrow;
+    escrow.status = EscrowStatus::Resolved;
+    escrow.refund_percentage = Some(refund_percentage);
+
+    record_relationship_settlement(
+        &mut ctx.accounts.relationship,
+        escrow.amount,
+        None,
+        clock.unix_timestamp,
+    );
+
+    msg!("SLA settlement: {}% refund", refund_percentage);
+
+    emit!(SlaSettled {
+        escrow: escrow.key(),
+        transaction_id: escrow.transaction_id.clone(),
+        latency_ms,
+        availability_bps,
+        deadline_missed,
+        refund_percentage,
+        refund_amount,
+        payment_amount,
+        verifier: ctx.accounts.verifier.key(),
+        bond_returned,
+        bond_forfeited,
+    });
+
+    Ok(())
+}
+
+
+pub(crate) fn settle_unresolved(ctx: Context<SettleUnresolved>) -> Result<()> {
+    let escrow = &ctx.accounts.escrow;
+    let clock = Clock::get()?;
+
+    require!(
+        escrow.status == EscrowStatus::Disputed,
+        EscrowError::InvalidStatus
+    );
+    require!(
+        escrow.resolution_deadline > 0 && clock.unix_timestamp >= escrow.resolution_deadline,
+        EscrowError::ResolutionDeadlineNotReached
+    );
+
+    let refund_percentage = ctx.accounts.config.default_refund_percentage;
+    let refund_to = refund_account(
+        &ctx.accounts.escrow,
+        ctx.accounts.agent.to_account_info(),
+        ctx.accounts.agent_vault.clone(),
+    );
+    let (refund_amount, payment_amount, bond_returned, bond_forfeited) = distribute_settlement(
+        &ctx.accounts.escrow.to_account_info(),
+        &ctx.accounts.escrow,
+        &refund_to,
+        &ctx.accounts.payout.to_account_info(),
+        refund_percentage,
+    )?;
+    settle_provider_bond(&ctx.accounts.provider_bond, &refund_to, &ctx.accounts.escrow, 0)?;
+
+    let escrow = &mut ctx.accounts.escrow;
+    escrow.status = EscrowStatus::Resolved;
+    escrow.refund_percentage = Some(refund_percentage);
+
+    record_relationship_settlement(
+        &mut ctx.accounts.relationship,
+        escrow.amount,
+        None,
+        clock.unix_timestamp,
+    );
+
+    msg!("Unresolved dispute settled by default: {}% refund", refund_percentage);
+
+    emit!(UnresolvedSettled {
+        escrow: escrow.key(),
+        transaction_id: escrow.transaction_id.clone(),
+        refund_percentage,
+        refund_amount,
+        payment_amount,
+        bond_returned,
+        bond_forfeited,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+
+/// Refund percentage for a measurement under `SLA_REFUND_TABLE`
+///
+/// A zero `max_latency_ms` means the SLA only covers availability.
+pub(crate) fn sla_refund_percentage(max_latency_ms: u32, latency_ms: u32, availability_bps: u16) -> u8 {
+    SLA_REFUND_TABLE
+        .iter()
+        .find(|(min_availability, latency_multiple, _)| {
+            availability_bps >= *min_availability
+                && (max_latency_ms == 0
+                    || latency_ms as u64 <= max_latency_ms as u64 * *latency_multiple as u64)
+        })
+        .map_or(100, |(_, _, refund_percentage)| *refund_percentage)
+}
+
+/// Split an escrowed amount into the agent's refund and the API's payment
+///
+/// Rounding favours the API; the two always sum to `amount`.
+pub(crate) fn split_escrow_amount(amount: u64, refund_percentage: u8) -> Result<(u64, u64)> {
+    let refund = (amount as u128)
+        .checked_mul(refund_percentage as u128)
+        .ok_or(EscrowError::ArithmeticOverflow)?
+        .checked_div(100)
+        .ok_or(EscrowError::ArithmeticOverflow)? as u64;
+
+    Ok((refund, amount - refund))
+}
+
+/// Pay out a settled escrow and its dispute bond by `refund_percentage`
+///
+/// The refund shares go to `refund_to` (see `refund_account`), the rest to
+/// the provider's `payout`. Returns `(refund_amount, payment_amount,
+/// bond_returned, bond_forfeited)`.
+pub(crate) fn distribute_settlement(
+    escrow_info: &AccountInfo,
+    escrow: &Escrow,
+    refund_to: &AccountInfo,
+    payout: &AccountInfo,
+    refund_percentage: u8,
+) -> Result<(u64, u64, u64, u64)> {
+    let (refund_amount, payment_amount) = split_escrow_amount(escrow.amount, refund_percentage)?;
+    let (bond_returned, bond_forfeited) = split_dispute_bond(escrow.dispute_bond, refund_percentage)?;
+
+    // Direct lamport moves: the escrow PDA carries data, so system transfers can't debit it
+    transfer_lamports(escrow_info, refund_to, refund_amount + bond_returned)?;
+    transfer_lamports(escrow_info, payout, payment_amount + bond_forfeited)?;
+
+    Ok((refund_amount, payment_amount, bond_returned, bond_forfeited))
+}
+
+/// Split a dispute bond by outcome
+///
+/// The agent gets back the same share as its refund (all of it when the
+/// dispute is won outright); the remainder is forfeited to the API.
+pub(crate) fn split_dispute_bond(bond: u64, refund_percentage: u8) -> Result<(u64, u64)> {
+    let returned = (bond as u128)
+        .checked_mul(refund_percentage as u128)
+        .ok_or(EscrowError::ArithmeticOverflow)?
+        .checked_div(100)
+        .ok_or(EscrowError::ArithmeticOverflow)? as u64;
+
+    Ok((returned, bond - returned))
+}
+
+/// Reject `verifier` if the escrow's accepted verifiers don't include it
+///
+/// Checked against the list snapshotted at `initialize_escrow`, so a later
+/// `update_provider` can't swap the judge on an escrow that is already open.
+/// Escrows whose provider listed none fall back to the config's
+/// `default_verifiers`; with both empty no verifier is accepted.
+pub(crate) fn require_accepted_verifier(
+    escrow: &Escrow,
+    config: &ProgramConfig,
+    verifier: &Pubkey,
+) -> Result<()> {
+    let lists_any = |keys: &[Pubkey]| keys.iter().any(|key| *key != Pubkey::default());
+    let accepted = if lists_any(&escrow.accepted_verifiers) {
+        &escrow.accepted_verifiers
+    } else {
+        &config.default_verifiers
+    };
+    require!(
+        *verifier != Pubkey::default() && accepted.contains(verifier),
+        EscrowError::VerifierNotAccepted
+    );
+    Ok(())
+}
+
+/// Account refunds for `escrow` go to: back into the vault that funded it,
+/// otherwise the agent's wallet
+pub(crate) fn refund_account<'info>(
+    escrow: &Escrow,
+    agent: AccountInfo<'info>,
+    agent_vault: AccountInfo<'info>,
+) -> AccountInfo<'info> {
+    if escrow.funded_from_vault {
+        agent_vault
+    } else {
+        agent
+    }
+}
+
+/// Release an escrow's bond reservation, paying `penalty` from it to the agent
+pub(crate) fn settle_provider_bond(
+    provider_bond: &AccountInfo,
+    agent: &AccountInfo,
+    escrow: &Escrow,
+    penalty: u64,
+) -> Result<()> {
+    require!(penalty <= escrow.bond_reserved, EscrowError::InsufficientProviderBond);
+    if escrow.bond_reserved == 0 {
+        return Ok(());
+    }
+    require_keys_eq!(*provider_bond.owner, crate::ID, ErrorCode::ConstraintOwner);
+
+    {
+        let mut data = provider_bond.try_borrow_mut_data()?;
+        let mut bond = ProviderBond::try_deserialize(&mut &data[..])?;
+        require_current_version(&bond)?;
+        release_bond(&mut bond, escrow.bond_reserved, penalty)?;
+        bond.try_serialize(&mut &mut data[..])?;
+    }
+
+    transfer_lamports(provider_bond, agent, penalty)
+}
+
+/// Message a verifier signs to resolve a dispute on `escrow`
+///
+/// Format: "{transaction_id}:{quality_score}:{refund_percentage}", followed
+/// in order by ":{agreement_hash}" when a work agreement exists and
+/// ":{response_hash}" when delivery was confirmed, both in lowercase hex.
+pub(crate) fn verifier_message(escrow: &Escrow, quality_score: u8, refund_percentage: u8) -> String {
+    let mut message = format!("{}:{}:{}", escrow.transaction_id, quality_score, refund_percentage);
+    for hash in [escrow.agreement_hash, escrow.response_hash].iter().flatten() {
+        message.push(':');
+        message.push_str(&hex_encode(hash));
+    }
+    message
+}
+
+/// Message a verifier signs to settle `escrow` against its SLA
+///
+/// Format: "sla:{transaction_id}:{max_latency_ms}:{deadline}:{latency_ms}:{availability_bps}",
+/// followed by ":{agreement_hash}" and ":{response_hash}" in lowercase hex
+/// when set, so a measurement only applies to the terms it was taken under.
+pub(crate) fn sla_message(escrow: &Escrow, latency_ms: u32, availability_bps: u16) -> String {
+    let mut message = format!(
+        "sla:{}:{}:{}:{}:{}",
+        escrow.transaction_id, escrow.sla_max_latency_ms, escrow.sla_deadline, latency_ms, availability_bps
+    );
+    for hash in [escrow.agreement_hash, escrow.response_hash].iter().flatten() {
+        message.push(':');
+        message.push_str(&hex_encode(hash));
+    }
+    message
+}
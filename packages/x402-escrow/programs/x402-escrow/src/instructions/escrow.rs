@@ -0,0 +1,896 @@
+//! Escrow lifecycle: opening, work agreements, delivery, release and disputes
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+
+use crate::*;
+
+#[derive(Accounts)]
+#[instruction(amount: u64, time_lock: i64, transaction_id: String)]
+pub struct InitializeEscrow<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Escrow::INIT_SPACE,
+        seeds = [b"escrow", transaction_id.as_bytes()],
+        bump
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    /// CHECK: Agent identity; must sign as `authority` unless a session key does
+    pub agent: AccountInfo<'info>,
+
+    /// The agent itself, or one of its session keys; pays account rent
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: Session PDA; only read when `authority` is not the agent
+    #[account(mut, seeds = [b"session", agent.key().as_ref(), authority.key().as_ref()], bump)]
+    pub session: AccountInfo<'info>,
+
+    /// CHECK: Agent vault PDA; only read when `fund_from_vault` is set
+    #[account(mut, seeds = [b"vault", agent.key().as_ref()], bump)]
+    pub agent_vault: AccountInfo<'info>,
+
+    /// CHECK: API wallet address
+    pub api: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"reputation", agent.key().as_ref()],
+        bump = agent_reputation.bump,
+        constraint = agent_reputation.version == EntityReputation::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub agent_reputation: Account<'info, EntityReputation>,
+
+    #[account(
+        seeds = [b"reputation", api.key().as_ref()],
+        bump = api_reputation.bump
+    )]
+    pub api_reputation: Account<'info, EntityReputation>,
+
+    #[account(
+        mut,
+        seeds = [b"rate_limit", agent.key().as_ref()],
+        bump = rate_limiter.bump,
+        constraint = rate_limiter.version == RateLimiter::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub rate_limiter: Account<'info, RateLimiter>,
+
+    #[account(
+        mut,
+        seeds = [b"relationship", agent.key().as_ref(), api.key().as_ref()],
+        bump = relationship.bump,
+        constraint = relationship.version == Relationship::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub relationship: Account<'info, Relationship>,
+
+    /// CHECK: Provider's limits PDA; may be uninitialized if none are published
+    #[account(seeds = [b"provider_limits", api.key().as_ref()], bump)]
+    pub provider_limits: AccountInfo<'info>,
+
+    /// CHECK: Provider's registration PDA; may be uninitialized if unregistered
+    #[account(seeds = [b"provider", api.key().as_ref()], bump)]
+    pub provider: AccountInfo<'info>,
+
+    /// CHECK: Provider's bond PDA; may be uninitialized if the provider posts none
+    #[account(mut, seeds = [b"bond", api.key().as_ref()], bump)]
+    pub provider_bond: AccountInfo<'info>,
+
+    /// CHECK: Provider's penalty PDA; may be uninitialized if never penalized
+    #[account(seeds = [b"penalties", api.key().as_ref()], bump)]
+    pub provider_penalties: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateWorkAgreement<'info> {
+    #[account(
+        init,
+        payer = agent,
+        space = 8 + WorkAgreement::INIT_SPACE,
+        seeds = [b"agreement", escrow.key().as_ref()],
+        bump
+    )]
+    pub agreement: Account<'info, WorkAgreement>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.transaction_id.as_bytes()],
+        bump = escrow.bump,
+        has_one = agent @ EscrowError::Unauthorized,
+        has_one = api @ EscrowError::Unauthorized,
+        constraint = escrow.version == Escrow::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(mut)]
+    pub agent: Signer<'info>,
+
+    /// Provider co-signs the terms it will be judged against
+    pub api: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ConfirmDelivery<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.transaction_id.as_bytes()],
+        bump = escrow.bump,
+        has_one = api @ EscrowError::Unauthorized,
+        constraint = escrow.version == Escrow::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    pub api: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetEscrowSla<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.transaction_id.as_bytes()],
+        bump = escrow.bump,
+        has_one = agent @ EscrowError::Unauthorized,
+        has_one = api @ EscrowError::Unauthorized,
+        constraint = escrow.version == Escrow::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    pub agent: Signer<'info>,
+
+    /// Provider co-signs the SLA it will be settled against
+    pub api: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseFunds<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.transaction_id.as_bytes()],
+        bump = escrow.bump,
+        constraint = escrow.version == Escrow::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(mut)]
+    pub agent: Signer<'info>,
+
+    /// CHECK: API wallet address
+    #[account(mut)]
+    pub api: AccountInfo<'info>,
+
+    /// CHECK: Receives the payment; fixed at escrow creation
+    #[account(mut, address = escrow.payout @ EscrowError::Unauthorized)]
+    pub payout: AccountInfo<'info>,
+
+    /// CHECK: Provider's bond PDA; only read when the escrow reserved from it
+    #[account(mut, seeds = [b"bond", escrow.api.as_ref()], bump)]
+    pub provider_bond: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"relationship", escrow.agent.as_ref(), escrow.api.as_ref()],
+        bump = relationship.bump,
+        constraint = relationship.version == Relationship::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub relationship: Account<'info, Relationship>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MarkDisputed<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.transaction_id.as_bytes()],
+        bump = escrow.bump,
+        constraint = escrow.version == Escrow::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        seeds = [b"reputation", agent.key().as_ref()],
+        bump = reputation.bump,
+        constraint = reputation.version == EntityReputation::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub reputation: Account<'info, EntityReputation>,
+
+    #[account(
+        mut,
+        seeds = [b"rate_limit", agent.key().as_ref()],
+        bump = rate_limiter.bump,
+        constraint = rate_limiter.version == RateLimiter::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub rate_limiter: Account<'info, RateLimiter>,
+
+    /// CHECK: Agent identity, checked against the escrow
+    pub agent: AccountInfo<'info>,
+
+    /// The agent itself, or one of its session keys
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: Session PDA; only read when `authority` is not the agent
+    #[account(mut, seeds = [b"session", agent.key().as_ref(), authority.key().as_ref()], bump)]
+    pub session: AccountInfo<'info>,
+
+    /// CHECK: Agent vault PDA; only read when a session key signs
+    #[account(mut, seeds = [b"vault", agent.key().as_ref()], bump)]
+    pub agent_vault: AccountInfo<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub(crate) fn initialize_escrow(
+    ctx: Context<InitializeEscrow>,
+    amount: u64,
+    time_lock: i64,
+    transaction_id: String,
+    metadata: Option<EscrowMetadata>,
+    fund_from_vault: bool,
+) -> Result<()> {
+    // Validate inputs
+    require!(
+        amount >= MIN_ESCROW_AMOUNT,
+        EscrowError::InvalidAmount
+    );
+    require!(
+        amount <= MAX_ESCROW_AMOUNT,
+        EscrowError::AmountTooLarge
+    );
+    require!(
+        !transaction_id.is_empty() && transaction_id.len() <= 64,
+        EscrowError::InvalidTransactionId
+    );
+    let memo_fits = match &metadata {
+        Some(metadata) => metadata.memo.len() <= MAX_MEMO_LEN,
+        None => true,
+    };
+    require!(memo_fits, EscrowError::InvalidEscrowMetadata);
+
+    // Both parties must clear the other side's published reputation floor
+    let agent_reputation = &ctx.accounts.agent_reputation;
+    let api_reputation = &ctx.accounts.api_reputation;
+    require!(
+        agent_reputation.reputation_score >= api_reputation.min_counterparty_score,
+        EscrowError::ReputationTooLow
+    );
+    require!(
+        api_reputation.reputation_score >= agent_reputation.min_counterparty_score,
+        EscrowError::ReputationTooLow
+    );
+
+    // Registered providers get paid at their payout address, on their terms
+    let provider = load_provider(&ctx.accounts.provider)?;
+    let (payout, dispute_window, accepted_verifiers) = match &provider {
+        Some(provider) => {
+            require!(amount >= provider.default_price, EscrowError::ProviderTermsNotMet);
+            require!(
+                agent_reputation.reputation_score >= provider.min_agent_reputation,
+                EscrowError::ReputationTooLow
+            );
+            (provider.payout, provider.dispute_window, provider.accepted_verifiers)
+        }
+        None => (ctx.accounts.api.key(), 0, [Pubkey::default(); MAX_ACCEPTED_VERIFIERS]),
+    };
+
+    // A zero time lock takes the provider's published default
+    let time_lock = match (&provider, time_lock) {
+        (Some(provider), 0) => provider.default_time_lock,
+        _ => time_lock,
+    };
+    require!(
+        (MIN_TIME_LOCK..=MAX_TIME_LOCK).contains(&time_lock),
+        EscrowError::InvalidTimeLock
+    );
+
+    // Bonded providers back every escrow with an equal reservation
+    let bond_reserved = reserve_provider_bond(&ctx.accounts.provider_bond, amount)?;
+
+    let clock = Clock::get()?;
+    let penalties = load_provider_penalties(&ctx.accounts.provider_penalties)?;
+    require!(
+        !penalties.is_some_and(|penalties| provider_is_suspended(&penalties, clock.unix_timestamp)),
+        EscrowError::ProviderSuspended
+    );
+
+    consume_rate_limit(&mut ctx.accounts.rate_limiter, clock.unix_timestamp)?;
+    record_window_transaction(&mut ctx.accounts.agent_reputation, clock.unix_timestamp);
+
+    // Provider-defined limits for this agent, on top of the global ones
+    let provider_limits = load_provider_limits(&ctx.accounts.provider_limits)?;
+    consume_provider_limit(
+        &mut ctx.accounts.relationship,
+        provider_limits.as_ref(),
+        amount,
+        clock.unix_timestamp,
+    )?;
+
+    // Session keys have no other source of funds; the agent chooses
+    let signed_by_agent = ctx.accounts.authority.key() == ctx.accounts.agent.key();
+    let funded_from_vault = escrow_funded_from_vault(signed_by_agent, fund_from_vault)?;
+
+    // Initialize escrow state
+    {
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.agent = ctx.accounts.agent.key();
+        escrow.api = ctx.accounts.api.key();
+        escrow.amount = amount;
+        escrow.status = EscrowStatus::Active;
+        escrow.created_at = clock.unix_timestamp;
+        escrow.expires_at = clock.unix_timestamp + time_lock;
+        escrow.transaction_id = transaction_id.clone();
+        escrow.bump = ctx.bumps.escrow;
+        escrow.version = Escrow::CURRENT_VERSION;
+        escrow.dispute_bond = 0;
+        escrow.agreement_hash = None;
+        escrow.response_hash = None;
+        escrow.record_count = 0;
+        escrow.delivered_at = 0;
+        escrow.payout = payout;
+        escrow.sla_max_latency_ms = 0;
+        escrow.sla_deadline = 0;
+        escrow.bond_reserved = bond_reserved;
+        escrow.dispute_window = dispute_window;
+        escrow.resolution_deadline = 0;
+        escrow.metadata = metadata.clone();
+        escrow.funded_from_vault = funded_from_vault;
+        escrow.accepted_verifiers = accepted_verifiers;
+    }
+
+    // Verify transfer amount covers rent before executing
+    let rent = Rent::get()?;
+    let min_rent = rent.minimum_balance(8 + Escrow::INIT_SPACE);
+    require!(
+        amount >= min_rent,
+        EscrowError::InsufficientRentReserve
+    );
+
+    // Transfer SOL to escrow PDA, holding session keys to their limits
+    let accounts = &ctx.accounts;
+    if !signed_by_agent {
+        charge_session(
+            &accounts.session,
+            accounts.agent.key,
+            accounts.authority.key,
+            amount,
+            Some(accounts.api.key),
+            clock.unix_timestamp,
+        )?;
+    }
+    if funded_from_vault {
+        withdraw_from_agent_vault(&accounts.agent_vault, &accounts.escrow.to_account_info(), amount)?;
+    } else {
+        let cpi_context = CpiContext::new(
+            accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: accounts.authority.to_account_info(),
+                to: accounts.escrow.to_account_info(),
+            },
+        );
+        anchor_lang::system_program::transfer(cpi_context, amount)?;
+    }
+
+    let expires_at = clock.unix_timestamp + time_lock;
+    msg!("Escrow initialized: {} SOL locked", amount as f64 / 1_000_000_000.0);
+    msg!("Expires at: {}", expires_at);
+
+    let escrow = &ctx.accounts.escrow;
+    emit!(EscrowInitialized {
+        escrow: escrow.key(),
+        agent: escrow.agent,
+        api: escrow.api,
+        amount: escrow.amount,
+        expires_at: escrow.expires_at,
+        transaction_id: transaction_id,
+        metadata,
+        funded_from_vault,
+    });
+
+    Ok(())
+}
+
+
+pub(crate) fn create_work_agreement(
+    ctx: Context<CreateWorkAgreement>,
+    query: String,
+    required_fields: u8,
+    min_records: u32,
+    max_age_days: u32,
+    min_quality_score: u8,
+) -> Result<()> {
+    // Terms are fixed before anything is delivered against them
+    require!(
+        ctx.accounts.escrow.status == EscrowStatus::Active && ctx.accounts.escrow.delivered_at == 0,
+        EscrowError::InvalidStatus
+    );
+    require!(
+        !query.is_empty() && query.len() <= 128,
+        EscrowError::InvalidWorkAgreement
+    );
+    require!(min_quality_score <= 100, EscrowError::InvalidQualityScore);
+
+    let agreement = &mut ctx.accounts.agreement;
+    agreement.escrow = ctx.accounts.escrow.key();
+    agreement.query = query;
+    agreement.required_fields = required_fields;
+    agreement.min_records = min_records;
+    agreement.max_age_days = max_age_days;
+    agreement.min_quality_score = min_quality_score;
+    agreement.created_at = Clock::get()?.unix_timestamp;
+    agreement.bump = ctx.bumps.agreement;
+    agreement.version = WorkAgreement::CURRENT_VERSION;
+
+    let agreement_hash = work_agreement_hash(agreement);
+    ctx.accounts.escrow.agreement_hash = Some(agreement_hash);
+
+    msg!("Work agreement recorded for {}", ctx.accounts.escrow.transaction_id);
+
+    emit!(WorkAgreementCreated {
+        escrow: ctx.accounts.escrow.key(),
+        agreement: ctx.accounts.agreement.key(),
+        agreement_hash,
+    });
+
+    Ok(())
+}
+
+
+pub(crate) fn confirm_delivery(
+    ctx: Context<ConfirmDelivery>,
+    response_hash: [u8; 32],
+    record_count: u32,
+) -> Result<()> {
+    let escrow = &mut ctx.accounts.escrow;
+    let clock = Clock::get()?;
+
+    require!(
+        escrow.status == EscrowStatus::Active,
+        EscrowError::InvalidStatus
+    );
+    require!(escrow.delivered_at == 0, EscrowError::DeliveryAlreadyConfirmed);
+    require!(
+        clock.unix_timestamp < delivery_deadline(escrow),
+        EscrowError::DeliveryConfirmationTooLate
+    );
+
+    escrow.response_hash = Some(response_hash);
+    escrow.record_count = record_count;
+    escrow.delivered_at = clock.unix_timestamp;
+
+    msg!("Delivery confirmed for {}", escrow.transaction_id);
+
+    emit!(DeliveryConfirmed {
+        escrow: escrow.key(),
+        api: escrow.api,
+        response_hash,
+        record_count,
+        delivered_at: escrow.delivered_at,
+        dispute_window_end: dispute_window_end(escrow),
+    });
+
+    Ok(())
+}
+
+
+pub(crate) fn set_escrow_sla(
+    ctx: Context<SetEscrowSla>,
+    max_latency_ms: u32,
+    deadline: i64,
+) -> Result<()> {
+    let escrow = &mut ctx.accounts.escrow;
+    let clock = Clock::get()?;
+
+    require!(
+        escrow.status == EscrowStatus::Active && escrow.delivered_at == 0,
+        EscrowError::InvalidStatus
+    );
+    require!(
+        (max_latency_ms > 0 || deadline > 0)
+            && (deadline == 0 || (deadline > clock.unix_timestamp && deadline <= escrow.expires_at)),
+        EscrowError::InvalidSlaTerms
+    );
+
+    escrow.sla_max_latency_ms = max_latency_ms;
+    escrow.sla_deadline = deadline;
+
+    emit!(EscrowSlaSet {
+        escrow: escrow.key(),
+        max_latency_ms,
+        deadline,
+    });
+
+    Ok(())
+}
+
+
+pub(crate) fn release_funds(ctx: Context<ReleaseFunds>) -> Result<()> {
+    let escrow = &mut ctx.accounts.escrow;
+    let clock = Clock::get()?;
+
+    require!(
+        escrow.status == EscrowStatus::Active,
+        EscrowError::InvalidStatus
+    );
+
+    // Check if caller is agent OR auto-release has opened
+    let is_agent = ctx.accounts.agent.key() == escrow.agent;
+    let time_lock_expired = clock.unix_timestamp >= auto_release_at(escrow);
+
+    // If not agent, time lock must have expired
+    if !is_agent {
+        require!(time_lock_expired, EscrowError::TimeLockNotExpired);
+    }
+
+    require!(is_agent || time_lock_expired, EscrowError::Unauthorized);
+
+    // Transfer full amount to API
+    transfer_lamports(
+        &escrow.to_account_info(),
+        &ctx.accounts.payout.to_account_info(),
+        escrow.amount,
+    )?;
+
+    settle_provider_bond(
+        &ctx.accounts.provider_bond,
+        &ctx.accounts.agent.to_account_info(),
+        &ctx.accounts.escrow,
+        0,
+    )?;
+
+    let escrow = &mut ctx.accounts.escrow;
+    escrow.status = EscrowStatus::Released;
+
+    msg!("Funds released to API: {} SOL", escrow.amount as f64 / 1_000_000_000.0);
+
+    let clock = Clock::get()?;
+    record_relationship_settlement(
+        &mut ctx.accounts.relationship,
+        escrow.amount,
+        None,
+        clock.unix_timestamp,
+    );
+
+    emit!(FundsReleased {
+        escrow: escrow.key(),
+        transaction_id: escrow.transaction_id.clone(),
+        amount: escrow.amount,
+        api: escrow.api,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+
+pub(crate) fn mark_disputed(ctx: Context<MarkDisputed>) -> Result<()> {
+    let escrow = &mut ctx.accounts.escrow;
+    let reputation = &mut ctx.accounts.reputation;
+
+    require!(
+        escrow.status == EscrowStatus::Active,
+        EscrowError::InvalidStatus
+    );
+
+    require!(
+        ctx.accounts.agent.key() == escrow.agent,
+        EscrowError::Unauthorized
+    );
+
+    // Check if dispute window is still open (see `dispute_window_end`)
+    let clock = Clock::get()?;
+    require!(
+        clock.unix_timestamp < dispute_window_end(escrow),
+        EscrowError::DisputeWindowExpired
+    );
+
+    // Disputes draw on their own daily cap, not the transaction budget
+    let rate_limiter = &mut ctx.accounts.rate_limiter;
+    let (disputes_remaining, dispute_day_limit) =
+        consume_dispute_limit(rate_limiter, clock.unix_timestamp)?;
+
+    emit!(DisputeRateLimitConsumed {
+        entity: rate_limiter.entity,
+        disputes_remaining,
+        dispute_day_limit,
+        timestamp: clock.unix_timestamp,
+    });
+
+    // Calculate dispute cost from the recent dispute rate, then record this one
+    let dispute_cost = calculate_dispute_cost(reputation, &ctx.accounts.config, clock.unix_timestamp);
+    record_window_dispute(reputation, clock.unix_timestamp);
+
+    // Hold the dispute cost as a bond with the escrow until resolution.
+    // The agent pays from its wallet; session keys can only spend from
+    // the vault. Either way the returned share follows `refund_account`.
+    let authority = &ctx.accounts.authority;
+    let signed_by_agent = authority.key() == ctx.accounts.agent.key();
+    if signed_by_agent {
+        require!(
+            authority.lamports() >= dispute_cost,
+            EscrowError::InsufficientDisputeFunds
+        );
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: authority.to_account_info(),
+                to: escrow.to_account_info(),
+            },
+        );
+        anchor_lang::system_program::transfer(cpi_context, dispute_cost)?;
+    } else {
+        charge_session(
+            &ctx.accounts.session,
+            ctx.accounts.agent.key,
+            authority.key,
+            dispute_cost,
+            None,
+            clock.unix_timestamp,
+        )?;
+        withdraw_from_agent_vault(&ctx.accounts.agent_vault, &escrow.to_account_info(), dispute_cost)?;
+    }
+    escrow.dispute_bond = dispute_cost;
+
+    // Update reputation - record dispute filed
+    reputation.disputes_filed = reputation.disputes_filed.saturating_add(1);
+
+    escrow.status = EscrowStatus::Disputed;
+    escrow.resolution_deadline = clock
+        .unix_timestamp
+        .saturating_add(ctx.accounts.config.resolution_period);
+
+    msg!("Escrow marked as disputed (bond: {} lamports)", dispute_cost);
+
+    emit!(DisputeMarked {
+        escrow: escrow.key(),
+        agent: escrow.agent,
+        transaction_id: escrow.transaction_id.clone(),
+        timestamp: clock.unix_timestamp,
+        bond_amount: dispute_cost,
+        resolution_deadline: escrow.resolution_deadline,
+    });
+
+    Ok(())
+}
+
+
+/// Dispute cost for `reputation` under the configured tier table
+///
+/// Priced on the recent dispute rate (see `rolling_dispute_rate`) so that
+/// old behaviour ages out and new abuse shows up quickly.
+pub(crate) fn calculate_dispute_cost(reputation: &EntityReputation, config: &ProgramConfig, now: i64) -> u64 {
+    let multiplier = match rolling_dispute_rate(reputation, now) {
+        Some(dispute_rate) => dispute_cost_multiplier(&config.dispute_cost_tiers, dispute_rate),
+        None => 1, // No recent transactions
+    };
+
+    config.base_dispute_cost.saturating_mul(multiplier as u64)
+}
+
+/// Multiplier of the first tier whose `max_rate` covers `dispute_rate`
+pub(crate) fn dispute_cost_multiplier(tiers: &[DisputeCostTier; DISPUTE_COST_TIERS], dispute_rate: u64) -> u16 {
+    tiers
+        .iter()
+        .find(|tier| dispute_rate <= tier.max_rate as u64)
+        .unwrap_or(&tiers[DISPUTE_COST_TIERS - 1])
+        .multiplier
+}
+
+/// Default tier table: the original 1x/2x/5x/10x schedule
+pub(crate) fn default_dispute_cost_tiers() -> [DisputeCostTier; DISPUTE_COST_TIERS] {
+    [
+        DisputeCostTier { max_rate: 20, multiplier: 1 },   // Normal dispute rate
+        DisputeCostTier { max_rate: 40, multiplier: 2 },   // High dispute rate
+        DisputeCostTier { max_rate: 60, multiplier: 5 },   // Very high dispute rate
+        DisputeCostTier { max_rate: 100, multiplier: 10 }, // Abuse pattern
+    ]
+}
+
+/// Advance the two-bucket dispute window to `now`
+pub(crate) fn roll_dispute_window(reputation: &mut EntityReputation, now: i64) {
+    if reputation.window_started_at == 0 {
+        reputation.window_started_at = now;
+        return;
+    }
+
+    let periods = (now - reputation.window_started_at) / DISPUTE_RATE_WINDOW;
+    if periods <= 0 {
+        return;
+    }
+
+    if periods == 1 {
+        reputation.prev_window_transactions = reputation.window_transactions;
+        reputation.prev_window_disputes = reputation.window_disputes;
+    } else {
+        reputation.prev_window_transactions = 0;
+        reputation.prev_window_disputes = 0;
+    }
+    reputation.window_transactions = 0;
+    reputation.window_disputes = 0;
+    reputation.window_started_at += periods * DISPUTE_RATE_WINDOW;
+}
+
+/// Count an escrow opened by this entity in the rolling window
+pub(crate) fn record_window_transaction(reputation: &mut EntityReputation, now: i64) {
+    roll_dispute_window(reputation, now);
+    reputation.window_transactions = reputation.window_transactions.saturating_add(1);
+}
+
+/// Count a dispute filed by this entity in the rolling window
+pub(crate) fn record_window_dispute(reputation: &mut EntityReputation, now: i64) {
+    roll_dispute_window(reputation, now);
+    reputation.window_disputes = reputation.window_disputes.saturating_add(1);
+}
+
+/// Disputes per 100 transactions over the last `DISPUTE_RATE_WINDOW`
+///
+/// Sliding-window estimate: the previous bucket is weighted by how much of
+/// it still overlaps the window ending at `now`. Returns `None` when there
+/// were no transactions in that span.
+pub(crate) fn rolling_dispute_rate(reputation: &EntityReputation, now: i64) -> Option<u64> {
+    if reputation.window_started_at == 0 {
+        return None;
+    }
+
+    let elapsed = (now - reputation.window_started_at).max(0);
+    let (prev_tx, prev_disputes, tx, disputes, into_bucket) = match elapsed / DISPUTE_RATE_WINDOW {
+        0 => (
+            reputation.prev_window_transactions,
+            reputation.prev_window_disputes,
+            reputation.window_transactions,
+            reputation.window_disputes,
+            elapsed,
+        ),
+        1 => (
+            reputation.window_transactions,
+            reputation.window_disputes,
+            0,
+            0,
+            elapsed - DISPUTE_RATE_WINDOW,
+        ),
+        _ => return None,
+    };
+
+    // Scale both counts by the window length to keep the weighting exact
+    let prev_weight = (DISPUTE_RATE_WINDOW - into_bucket) as u128;
+    let window = DISPUTE_RATE_WINDOW as u128;
+    let tx_scaled = prev_tx as u128 * prev_weight + tx as u128 * window;
+    let disputes_scaled = prev_disputes as u128 * prev_weight + disputes as u128 * window;
+
+    if tx_scaled == 0 {
+        return None;
+    }
+
+    Some((disputes_scaled * 100 / tx_scaled) as u64)
+}
+
+/// Decode the provider's limits if it has published any
+///
+/// The account address is fixed by seeds, so an agent cannot skip the
+/// check by omitting it; an uninitialized address simply means no limits.
+pub(crate) fn load_provider_limits(provider_limits: &AccountInfo) -> Result<Option<ProviderLimits>> {
+    if provider_limits.owner != &crate::ID {
+        return Ok(None);
+    }
+
+    ProviderLimits::load_any_version(provider_limits).map(Some)
+}
+
+/// Decode the provider's registration if it has published terms
+pub(crate) fn load_provider(provider: &AccountInfo) -> Result<Option<Provider>> {
+    if provider.owner != &crate::ID {
+        return Ok(None);
+    }
+
+    Provider::load_any_version(provider).map(Some)
+}
+
+/// Whether a new escrow is drawn from the agent's vault
+pub(crate) fn escrow_funded_from_vault(signed_by_agent: bool, fund_from_vault: bool) -> Result<bool> {
+    require!(signed_by_agent || fund_from_vault, EscrowError::SessionRequiresVault);
+    Ok(fund_from_vault)
+}
+
+/// Count a new escrow against the provider's limits for this agent
+///
+/// Open escrows and locked value are tracked whether or not the provider
+/// has limits, so limits published later see the true exposure.
+pub(crate) fn consume_provider_limit(
+    relationship: &mut Relationship,
+    provider_limits: Option<&ProviderLimits>,
+    amount: u64,
+    now: i64,
+) -> Result<()> {
+    let open_escrows = relationship.open_escrows.saturating_add(1);
+    let locked_value = relationship
+        .locked_value
+        .checked_add(amount)
+        .ok_or(EscrowError::ArithmeticOverflow)?;
+
+    if let Some(limits) = provider_limits {
+        if limits.max_escrows_per_hour > 0 {
+            let elapsed = now - relationship.tokens_refilled_at;
+            relationship.hour_tokens = refill_bucket(
+                relationship.hour_tokens,
+                limits.max_escrows_per_hour,
+                elapsed,
+                RATE_LIMIT_HOUR,
+            );
+            relationship.tokens_refilled_at = now;
+
+            require!(
+                relationship.hour_tokens >= RATE_LIMIT_HOUR as u64,
+                EscrowError::ProviderRateLimitExceeded
+            );
+            relationship.hour_tokens -= RATE_LIMIT_HOUR as u64;
+        }
+
+        require!(
+            limits.max_open_escrows == 0 || open_escrows <= limits.max_open_escrows,
+            EscrowError::ProviderRateLimitExceeded
+        );
+        require!(
+            limits.max_locked_value == 0 || locked_value <= limits.max_locked_value,
+            EscrowError::ProviderRateLimitExceeded
+        );
+    }
+
+    relationship.open_escrows = open_escrows;
+    relationship.locked_value = locked_value;
+
+    Ok(())
+}
+
+/// End of the period in which the agent may dispute
+///
+/// With a provider-set `dispute_window`, measured from confirmed delivery
+/// or else from creation. Without one, `DISPUTE_WINDOW` after delivery or
+/// else the time lock. Only registered providers set a window, so escrows
+/// with unregistered providers always take the default.
+pub(crate) fn dispute_window_end(escrow: &Escrow) -> i64 {
+    match (escrow.dispute_window, escrow.delivered_at) {
+        (0, 0) => escrow.expires_at,
+        (0, delivered_at) => delivered_at.saturating_add(DISPUTE_WINDOW),
+        (window, 0) => escrow.created_at.saturating_add(window),
+        (window, delivered_at) => delivered_at.saturating_add(window),
+    }
+}
+
+/// Last moment (exclusive) `confirm_delivery` may restart the dispute window
+///
+/// Before expiry and while the undelivered window is still open.
+pub(crate) fn delivery_deadline(escrow: &Escrow) -> i64 {
+    escrow.expires_at.min(dispute_window_end(escrow))
+}
+
+/// When anyone may release the escrow to the API
+///
+/// The time lock must have expired and no dispute may still be possible.
+pub(crate) fn auto_release_at(escrow: &Escrow) -> i64 {
+    escrow.expires_at.max(dispute_window_end(escrow))
+}
+
+/// SHA-256 over the agreed terms, Borsh-encoded in field order
+///
+/// Covers the escrow address and the scope fields only, so the hash does
+/// not change across account layout versions.
+pub(crate) fn work_agreement_hash(agreement: &WorkAgreement) -> [u8; 32] {
+    hashv(&[
+        agreement.escrow.as_ref(),
+        &(agreement.query.len() as u32).to_le_bytes(),
+        agreement.query.as_bytes(),
+        &[agreement.required_fields],
+        &agreement.min_records.to_le_bytes(),
+        &agreement.max_age_days.to_le_bytes(),
+        &[agreement.min_quality_score],
+    ])
+    .to_bytes()
+}
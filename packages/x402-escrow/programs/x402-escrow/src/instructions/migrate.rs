@@ -0,0 +1,85 @@
+//! In-place upgrades of accounts written under an older layout
+
+use anchor_lang::prelude::*;
+
+use crate::*;
+
+#[derive(Accounts)]
+pub struct MigrateAccount<'info> {
+    /// CHECK: Discriminator is checked in `migrate_account`; legacy layouts
+    /// may not deserialize as the current type until migrated
+    #[account(mut, owner = crate::ID)]
+    pub account: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub(crate) fn migrate_escrow(ctx: Context<MigrateAccount>) -> Result<()> {
+    let accounts = &ctx.accounts;
+    migrate_account::<Escrow>(&accounts.account, &accounts.payer, &accounts.system_program)
+}
+
+
+pub(crate) fn migrate_reputation(ctx: Context<MigrateAccount>) -> Result<()> {
+    let accounts = &ctx.accounts;
+    migrate_account::<EntityReputation>(&accounts.account, &accounts.payer, &accounts.system_program)
+}
+
+
+pub(crate) fn migrate_provider_penalties(ctx: Context<MigrateAccount>) -> Result<()> {
+    let accounts = &ctx.accounts;
+    migrate_account::<ProviderPenalties>(&accounts.account, &accounts.payer, &accounts.system_program)
+}
+
+
+pub(crate) fn migrate_rate_limiter(ctx: Context<MigrateAccount>) -> Result<()> {
+    let accounts = &ctx.accounts;
+    migrate_account::<RateLimiter>(&accounts.account, &accounts.payer, &accounts.system_program)
+}
+
+
+/// Rewrite an account in its current layout and stamp the new version
+pub(crate) fn migrate_account<'info, T: Versioned>(
+    account: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    let (state, from_version) = upgrade_account::<T>(&account.try_borrow_data()?)?;
+
+    // Rent is topped up by the size delta only: escrow balances also hold the
+    // escrowed amount, which must not count towards rent
+    let old_len = account.data_len();
+    let new_len = 8 + T::INIT_SPACE;
+    if old_len < new_len {
+        let rent = Rent::get()?;
+        let top_up = rent
+            .minimum_balance(new_len)
+            .saturating_sub(rent.minimum_balance(old_len));
+        if top_up > 0 {
+            let cpi_context = CpiContext::new(
+                system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: account.clone(),
+                },
+            );
+            anchor_lang::system_program::transfer(cpi_context, top_up)?;
+        }
+        account.resize(new_len)?;
+    }
+
+    state.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+
+    msg!("Migrated {} from v{} to v{}", account.key(), from_version, T::CURRENT_VERSION);
+
+    emit!(AccountMigrated {
+        account: account.key(),
+        from_version,
+        to_version: T::CURRENT_VERSION,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,21 @@
+//! Instruction accounts and handlers, grouped by the state they manage
+
+pub mod escrow;
+pub mod dispute;
+pub mod reputation;
+pub mod provider;
+pub mod rate_limit;
+pub mod stake;
+pub mod vault;
+pub mod config;
+pub mod migrate;
+
+pub use escrow::*;
+pub use dispute::*;
+pub use reputation::*;
+pub use provider::*;
+pub use rate_limit::*;
+pub use stake::*;
+pub use vault::*;
+pub use config::*;
+pub use migrate::*;
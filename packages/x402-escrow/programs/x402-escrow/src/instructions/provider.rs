@@ -0,0 +1,682 @@
+//! Provider registration, limits, bonds, penalties and appeals
+
+use anchor_lang::prelude::*;
+
+use crate::*;
+
+#[derive(Accounts)]
+pub struct InitProviderLimits<'info> {
+    #[account(
+        init,
+        payer = api,
+        space = 8 + ProviderLimits::INIT_SPACE,
+        seeds = [b"provider_limits", api.key().as_ref()],
+        bump
+    )]
+    pub provider_limits: Account<'info, ProviderLimits>,
+
+    #[account(mut)]
+    pub api: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetProviderLimits<'info> {
+    #[account(
+        mut,
+        seeds = [b"provider_limits", api.key().as_ref()],
+        bump = provider_limits.bump,
+        constraint = provider_limits.version == ProviderLimits::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub provider_limits: Account<'info, ProviderLimits>,
+
+    pub api: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterProvider<'info> {
+    #[account(
+        init,
+        payer = api,
+        space = 8 + Provider::INIT_SPACE,
+        seeds = [b"provider", api.key().as_ref()],
+        bump
+    )]
+    pub provider: Account<'info, Provider>,
+
+    #[account(mut)]
+    pub api: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateProvider<'info> {
+    #[account(
+        mut,
+        seeds = [b"provider", api.key().as_ref()],
+        bump = provider.bump,
+        constraint = provider.version == Provider::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub provider: Account<'info, Provider>,
+
+    pub api: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitProviderBond<'info> {
+    #[account(
+        init,
+        payer = api,
+        space = 8 + ProviderBond::INIT_SPACE,
+        seeds = [b"bond", api.key().as_ref()],
+        bump
+    )]
+    pub provider_bond: Account<'info, ProviderBond>,
+
+    #[account(mut)]
+    pub api: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateProviderBond<'info> {
+    #[account(
+        mut,
+        seeds = [b"bond", api.key().as_ref()],
+        bump = provider_bond.bump,
+        constraint = provider_bond.version == ProviderBond::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub provider_bond: Account<'info, ProviderBond>,
+
+    #[account(mut)]
+    pub api: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitProviderPenalties<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ProviderPenalties::INIT_SPACE,
+        seeds = [b"penalties", api.key().as_ref()],
+        bump
+    )]
+    pub provider_penalties: Account<'info, ProviderPenalties>,
+
+    /// CHECK: API wallet address
+    pub api: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FileAppeal<'info> {
+    #[account(
+        init,
+        payer = api,
+        space = 8 + Appeal::INIT_SPACE,
+        seeds = [b"appeal", api.key().as_ref()],
+        bump
+    )]
+    pub appeal: Account<'info, Appeal>,
+
+    #[account(
+        seeds = [b"penalties", api.key().as_ref()],
+        bump = provider_penalties.bump
+    )]
+    pub provider_penalties: Account<'info, ProviderPenalties>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(mut)]
+    pub api: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveAppeal<'info> {
+    #[account(
+        mut,
+        close = api,
+        seeds = [b"appeal", api.key().as_ref()],
+        bump = appeal.bump
+    )]
+    pub appeal: Account<'info, Appeal>,
+
+    #[account(
+        mut,
+        seeds = [b"penalties", api.key().as_ref()],
+        bump = provider_penalties.bump,
+        constraint = provider_penalties.version == ProviderPenalties::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub provider_penalties: Account<'info, ProviderPenalties>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = authority @ EscrowError::Unauthorized,
+        has_one = treasury @ EscrowError::Unauthorized
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// CHECK: Provider wallet; receives the bond on approval and the rent
+    #[account(mut)]
+    pub api: AccountInfo<'info>,
+
+    /// CHECK: Treasury wallet, checked against config
+    #[account(mut)]
+    pub treasury: AccountInfo<'info>,
+
+    pub authority: Signer<'info>,
+}
+
+pub(crate) fn init_provider_limits(
+    ctx: Context<InitProviderLimits>,
+    max_escrows_per_hour: u16,
+    max_open_escrows: u32,
+    max_locked_value: u64,
+) -> Result<()> {
+    let provider_limits = &mut ctx.accounts.provider_limits;
+
+    provider_limits.api = ctx.accounts.api.key();
+    provider_limits.max_escrows_per_hour = max_escrows_per_hour;
+    provider_limits.max_open_escrows = max_open_escrows;
+    provider_limits.max_locked_value = max_locked_value;
+    provider_limits.updated_at = Clock::get()?.unix_timestamp;
+    provider_limits.bump = ctx.bumps.provider_limits;
+    provider_limits.version = ProviderLimits::CURRENT_VERSION;
+
+    emit!(ProviderLimitsUpdated {
+        api: provider_limits.api,
+        max_escrows_per_hour,
+        max_open_escrows,
+        max_locked_value,
+    });
+
+    Ok(())
+}
+
+
+pub(crate) fn set_provider_limits(
+    ctx: Context<SetProviderLimits>,
+    max_escrows_per_hour: u16,
+    max_open_escrows: u32,
+    max_locked_value: u64,
+) -> Result<()> {
+    let provider_limits = &mut ctx.accounts.provider_limits;
+
+    provider_limits.max_escrows_per_hour = max_escrows_per_hour;
+    provider_limits.max_open_escrows = max_open_escrows;
+    provider_limits.max_locked_value = max_locked_value;
+    provider_limits.updated_at = Clock::get()?.unix_timestamp;
+
+    emit!(ProviderLimitsUpdated {
+        api: provider_limits.api,
+        max_escrows_per_hour,
+        max_open_escrows,
+        max_locked_value,
+    });
+
+    Ok(())
+}
+
+
+pub(crate) fn register_provider(
+    ctx: Context<RegisterProvider>,
+    payout: Pubkey,
+    endpoint_hash: [u8; 32],
+    default_time_lock: i64,
+    default_price: u64,
+    accepted_verifiers: Vec<Pubkey>,
+    min_agent_reputation: u16,
+) -> Result<()> {
+    let provider = &mut ctx.accounts.provider;
+    provider.api = ctx.accounts.api.key();
+    provider.bump = ctx.bumps.provider;
+    provider.version = Provider::CURRENT_VERSION;
+    provider.dispute_window = 0;
+
+    set_provider_terms(
+        provider,
+        payout,
+        endpoint_hash,
+        default_time_lock,
+        default_price,
+        accepted_verifiers,
+        min_agent_reputation,
+    )
+}
+
+
+pub(crate) fn update_provider(
+    ctx: Context<UpdateProvider>,
+    payout: Pubkey,
+    endpoint_hash: [u8; 32],
+    default_time_lock: i64,
+    default_price: u64,
+    accepted_verifiers: Vec<Pubkey>,
+    min_agent_reputation: u16,
+) -> Result<()> {
+    set_provider_terms(
+        &mut ctx.accounts.provider,
+        payout,
+        endpoint_hash,
+        default_time_lock,
+        default_price,
+        accepted_verifiers,
+        min_agent_reputation,
+    )
+}
+
+
+pub(crate) fn set_provider_dispute_window(ctx: Context<UpdateProvider>, dispute_window: i64) -> Result<()> {
+    require!(
+        dispute_window == 0 || (MIN_DISPUTE_WINDOW..=MAX_TIME_LOCK).contains(&dispute_window),
+        EscrowError::InvalidDisputeWindow
+    );
+
+    let provider = &mut ctx.accounts.provider;
+    provider.dispute_window = dispute_window;
+    provider.updated_at = Clock::get()?.unix_timestamp;
+
+    emit!(ProviderDisputeWindowUpdated {
+        api: provider.api,
+        dispute_window,
+    });
+
+    Ok(())
+}
+
+
+pub(crate) fn init_provider_bond(ctx: Context<InitProviderBond>) -> Result<()> {
+    let provider_bond = &mut ctx.accounts.provider_bond;
+
+    provider_bond.api = ctx.accounts.api.key();
+    provider_bond.balance = 0;
+    provider_bond.reserved = 0;
+    provider_bond.bump = ctx.bumps.provider_bond;
+    provider_bond.version = ProviderBond::CURRENT_VERSION;
+
+    msg!("Bond opened for provider {}", provider_bond.api);
+
+    Ok(())
+}
+
+
+pub(crate) fn post_bond(ctx: Context<UpdateProviderBond>, amount: u64) -> Result<()> {
+    require!(amount > 0, EscrowError::InvalidAmount);
+
+    let cpi_context = CpiContext::new(
+        ctx.accounts.system_program.to_account_info(),
+        anchor_lang::system_program::Transfer {
+            from: ctx.accounts.api.to_account_info(),
+            to: ctx.accounts.provider_bond.to_account_info(),
+        },
+    );
+    anchor_lang::system_program::transfer(cpi_context, amount)?;
+
+    let provider_bond = &mut ctx.accounts.provider_bond;
+    provider_bond.balance = provider_bond
+        .balance
+        .checked_add(amount)
+        .ok_or(EscrowError::ArithmeticOverflow)?;
+
+    emit!(ProviderBondUpdated {
+        api: provider_bond.api,
+        balance: provider_bond.balance,
+        reserved: provider_bond.reserved,
+    });
+
+    Ok(())
+}
+
+
+pub(crate) fn withdraw_bond(ctx: Context<UpdateProviderBond>, amount: u64) -> Result<()> {
+    let provider_bond = &mut ctx.accounts.provider_bond;
+    withdraw_unreserved_bond(provider_bond, amount)?;
+
+    transfer_lamports(
+        &provider_bond.to_account_info(),
+        &ctx.accounts.api.to_account_info(),
+        amount,
+    )?;
+
+    emit!(ProviderBondUpdated {
+        api: provider_bond.api,
+        balance: provider_bond.balance,
+        reserved: provider_bond.reserved,
+    });
+
+    Ok(())
+}
+
+
+pub(crate) fn init_provider_penalties(ctx: Context<InitProviderPenalties>) -> Result<()> {
+    let penalties = &mut ctx.accounts.provider_penalties;
+    let clock = Clock::get()?;
+
+    penalties.provider = ctx.accounts.api.key();
+    penalties.strike_count = 0;
+    penalties.suspended = false;
+    penalties.suspension_end = None;
+    penalties.total_refunds_issued = 0;
+    penalties.poor_quality_count = 0;
+    penalties.created_at = clock.unix_timestamp;
+    penalties.last_updated = clock.unix_timestamp;
+    penalties.bump = ctx.bumps.provider_penalties;
+    penalties.version = ProviderPenalties::CURRENT_VERSION;
+    penalties.last_strike_at = 0;
+
+    msg!("Penalties initialized for provider {}", penalties.provider);
+
+    Ok(())
+}
+
+
+pub(crate) fn file_appeal(ctx: Context<FileAppeal>, evidence_hash: [u8; 32]) -> Result<()> {
+    let clock = Clock::get()?;
+    let penalties = &ctx.accounts.provider_penalties;
+    require!(
+        provider_is_suspended(penalties, clock.unix_timestamp),
+        EscrowError::ProviderNotSuspended
+    );
+    let suspension_end = penalties.suspension_end.unwrap_or(i64::MAX);
+
+    let bond = ctx.accounts.config.appeal_bond;
+    let cpi_context = CpiContext::new(
+        ctx.accounts.system_program.to_account_info(),
+        anchor_lang::system_program::Transfer {
+            from: ctx.accounts.api.to_account_info(),
+            to: ctx.accounts.appeal.to_account_info(),
+        },
+    );
+    anchor_lang::system_program::transfer(cpi_context, bond)?;
+
+    let appeal = &mut ctx.accounts.appeal;
+    appeal.provider = ctx.accounts.api.key();
+    appeal.evidence_hash = evidence_hash;
+    appeal.bond = bond;
+    appeal.suspension_end = suspension_end;
+    appeal.filed_at = clock.unix_timestamp;
+    appeal.bump = ctx.bumps.appeal;
+    appeal.version = Appeal::CURRENT_VERSION;
+
+    msg!("Appeal filed by provider {}", appeal.provider);
+
+    emit!(AppealFiled {
+        provider: appeal.provider,
+        evidence_hash,
+        bond,
+        suspension_end,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+
+pub(crate) fn resolve_appeal(ctx: Context<ResolveAppeal>, approved: bool) -> Result<()> {
+    let clock = Clock::get()?;
+    let bond = ctx.accounts.appeal.bond;
+    let appeal_info = ctx.accounts.appeal.to_account_info();
+
+    let (bond_returned, bond_forfeited) = if approved {
+        transfer_lamports(&appeal_info, &ctx.accounts.api.to_account_info(), bond)?;
+        (bond, 0)
+    } else {
+        transfer_lamports(&appeal_info, &ctx.accounts.treasury, bond)?;
+        (0, bond)
+    };
+
+    let penalties = &mut ctx.accounts.provider_penalties;
+    if approved {
+        penalties.suspended = false;
+        penalties.suspension_end = None;
+        penalties.strike_count = 0;
+        penalties.last_updated = clock.unix_timestamp;
+
+        msg!("Provider {} reinstated", penalties.provider);
+
+        emit!(ProviderReinstated {
+            provider: penalties.provider,
+            timestamp: clock.unix_timestamp,
+        });
+    }
+
+    emit!(AppealResolved {
+        provider: penalties.provider,
+        approved,
+        bond_returned,
+        bond_forfeited,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+
+/// Validate and store provider terms, then emit `ProviderTermsUpdated`
+pub(crate) fn set_provider_terms(
+    provider: &mut Provider,
+    payout: Pubkey,
+    endpoint_hash: [u8; 32],
+    default_time_lock: i64,
+    default_price: u64,
+    accepted_verifiers: Vec<Pubkey>,
+    min_agent_reputation: u16,
+) -> Result<()> {
+    require!(
+        (MIN_TIME_LOCK..=MAX_TIME_LOCK).contains(&default_time_lock)
+            && default_price <= MAX_ESCROW_AMOUNT
+            && accepted_verifiers.len() <= MAX_ACCEPTED_VERIFIERS
+            && min_agent_reputation <= 1000,
+        EscrowError::InvalidProviderTerms
+    );
+
+    let mut verifier_slots = [Pubkey::default(); MAX_ACCEPTED_VERIFIERS];
+    verifier_slots[..accepted_verifiers.len()].copy_from_slice(&accepted_verifiers);
+
+    provider.payout = payout;
+    provider.endpoint_hash = endpoint_hash;
+    provider.default_time_lock = default_time_lock;
+    provider.default_price = default_price;
+    provider.accepted_verifiers = verifier_slots;
+    provider.min_agent_reputation = min_agent_reputation;
+    provider.updated_at = Clock::get()?.unix_timestamp;
+
+    emit!(ProviderTermsUpdated {
+        api: provider.api,
+        payout,
+        endpoint_hash,
+        default_time_lock,
+        default_price,
+        accepted_verifiers,
+        min_agent_reputation,
+    });
+
+    Ok(())
+}
+
+/// Reserve `amount` of the provider's bond for a new escrow
+///
+/// Returns the amount reserved, which is zero for providers that have not
+/// opened a bond. A bonded provider must have `amount` available.
+pub(crate) fn reserve_provider_bond(provider_bond: &AccountInfo, amount: u64) -> Result<u64> {
+    if provider_bond.owner != &crate::ID {
+        return Ok(0);
+    }
+
+    let mut data = provider_bond.try_borrow_mut_data()?;
+    let mut bond = ProviderBond::try_deserialize(&mut &data[..])?;
+    require_current_version(&bond)?;
+    reserve_bond(&mut bond, amount)?;
+    bond.try_serialize(&mut &mut data[..])?;
+
+    Ok(amount)
+}
+
+/// Move `amount` of unreserved collateral into the reservation
+pub(crate) fn reserve_bond(bond: &mut ProviderBond, amount: u64) -> Result<()> {
+    require!(
+        bond.balance.saturating_sub(bond.reserved) >= amount,
+        EscrowError::InsufficientProviderBond
+    );
+    bond.reserved = bond.reserved.checked_add(amount).ok_or(EscrowError::ArithmeticOverflow)?;
+    Ok(())
+}
+
+/// Release `reserved` and deduct the `penalty` paid out of it
+pub(crate) fn release_bond(bond: &mut ProviderBond, reserved: u64, penalty: u64) -> Result<()> {
+    require!(penalty <= reserved, EscrowError::InsufficientProviderBond);
+    bond.reserved = bond.reserved.saturating_sub(reserved);
+    bond.balance = bond.balance.checked_sub(penalty).ok_or(EscrowError::ArithmeticOverflow)?;
+    Ok(())
+}
+
+/// Deduct a withdrawal, which may only come from unreserved collateral
+pub(crate) fn withdraw_unreserved_bond(bond: &mut ProviderBond, amount: u64) -> Result<()> {
+    require!(amount > 0, EscrowError::InvalidAmount);
+    require!(
+        bond.balance.saturating_sub(bond.reserved) >= amount,
+        EscrowError::InsufficientProviderBond
+    );
+    bond.balance -= amount;
+    Ok(())
+}
+
+/// Decode the provider's penalty account if it has one
+///
+/// Providers that have never had a dispute resolved have no account and
+/// cannot be suspended.
+pub(crate) fn load_provider_penalties(provider_penalties: &AccountInfo) -> Result<Option<ProviderPenalties>> {
+    if provider_penalties.owner != &crate::ID {
+        return Ok(None);
+    }
+
+    ProviderPenalties::load_any_version(provider_penalties).map(Some)
+}
+
+/// Fill in a penalty account that `init_if_needed` has just created
+///
+/// Accounts that already existed must be at the current layout version.
+pub(crate) fn prepare_provider_penalties(
+    penalties: &mut ProviderPenalties,
+    api: Pubkey,
+    bump: u8,
+    now: i64,
+) -> Result<()> {
+    if penalties.provider == Pubkey::default() {
+        penalties.provider = api;
+        penalties.created_at = now;
+        penalties.last_updated = now;
+        penalties.bump = bump;
+        penalties.version = ProviderPenalties::CURRENT_VERSION;
+        return Ok(());
+    }
+
+    require_current_version(penalties)
+}
+
+/// Whether `penalties` bars the provider from new escrows at `now`
+///
+/// Suspensions lapse on their own once `suspension_end` has passed.
+pub(crate) fn provider_is_suspended(penalties: &ProviderPenalties, now: i64) -> bool {
+    penalties.suspended
+        && match penalties.suspension_end {
+            Some(end) => now < end,
+            None => true,
+        }
+}
+
+/// Record a resolved dispute against the provider's penalty account
+///
+/// Every `poor_quality_per_strike` poor-quality outcomes add a strike, and
+/// reaching `strikes_to_suspend` suspends the provider and clears strikes.
+pub(crate) fn record_provider_outcome(
+    penalties: &mut ProviderPenalties,
+    config: &ProgramConfig,
+    refund_amount: u64,
+    quality_score: u8,
+    now: i64,
+) {
+    if penalties.suspended && !provider_is_suspended(penalties, now) {
+        penalties.suspended = false;
+        penalties.suspension_end = None;
+    }
+
+    penalties.total_refunds_issued = penalties.total_refunds_issued.saturating_add(refund_amount);
+    penalties.last_updated = now;
+
+    if quality_score >= POOR_QUALITY_THRESHOLD {
+        return;
+    }
+
+    penalties.poor_quality_count = penalties.poor_quality_count.saturating_add(1);
+    decay_strikes(penalties, config.strike_decay_period, now);
+    if penalties.poor_quality_count.checked_rem(config.poor_quality_per_strike.max(1)) != Some(0) {
+        return;
+    }
+
+    penalties.strike_count = penalties.strike_count.saturating_add(1);
+    penalties.last_strike_at = now;
+
+    emit!(ProviderStrikeAdded {
+        provider: penalties.provider,
+        strike_count: penalties.strike_count,
+        poor_quality_count: penalties.poor_quality_count,
+        timestamp: now,
+    });
+
+    if penalties.strike_count < config.strikes_to_suspend.max(1) {
+        return;
+    }
+
+    let suspension_end = now.saturating_add(config.suspension_duration);
+    penalties.suspended = true;
+    penalties.suspension_end = Some(suspension_end);
+    penalties.strike_count = 0;
+
+    msg!("Provider {} suspended until {}", penalties.provider, suspension_end);
+
+    emit!(ProviderSuspended {
+        provider: penalties.provider,
+        suspension_end,
+        timestamp: now,
+    });
+}
+
+/// Remove one strike per `decay_period` elapsed since the last strike
+///
+/// The decay clock advances by whole periods only, so partial progress
+/// towards the next removal carries over.
+pub(crate) fn decay_strikes(penalties: &mut ProviderPenalties, decay_period: i64, now: i64) {
+    if penalties.strike_count == 0 || decay_period <= 0 {
+        return;
+    }
+
+    let periods = (now - penalties.last_strike_at).max(0) / decay_period;
+    let strikes_removed = periods.min(penalties.strike_count as i64) as u8;
+    if strikes_removed == 0 {
+        return;
+    }
+
+    penalties.strike_count -= strikes_removed;
+    penalties.last_strike_at += strikes_removed as i64 * decay_period;
+
+    emit!(StrikesDecayed {
+        provider: penalties.provider,
+        strikes_removed,
+        strike_count: penalties.strike_count,
+        timestamp: now,
+    });
+}
@@ -0,0 +1,383 @@
+//! Rate limits and the attested verification levels that size them
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions::ID as INSTRUCTIONS_ID;
+
+use crate::*;
+
+#[derive(Accounts)]
+pub struct InitRateLimiter<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + RateLimiter::INIT_SPACE,
+        seeds = [b"rate_limit", entity.key().as_ref()],
+        bump
+    )]
+    pub rate_limiter: Account<'info, RateLimiter>,
+
+    /// CHECK: Entity being rate limited
+    pub entity: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CheckRateLimit<'info> {
+    #[account(
+        mut,
+        seeds = [b"rate_limit", entity.key().as_ref()],
+        bump = rate_limiter.bump,
+        constraint = rate_limiter.version == RateLimiter::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub rate_limiter: Account<'info, RateLimiter>,
+
+    pub entity: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AddAttestor<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = authority @ EscrowError::Unauthorized
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Attestor::INIT_SPACE,
+        seeds = [b"attestor", attestor.key().as_ref()],
+        bump
+    )]
+    pub attestor_record: Account<'info, Attestor>,
+
+    /// CHECK: Attestor signing key being approved
+    pub attestor: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveAttestor<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = authority @ EscrowError::Unauthorized
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"attestor", attestor_record.attestor.as_ref()],
+        bump = attestor_record.bump
+    )]
+    pub attestor_record: Account<'info, Attestor>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AttestVerification<'info> {
+    #[account(
+        mut,
+        seeds = [b"rate_limit", rate_limiter.entity.as_ref()],
+        bump = rate_limiter.bump,
+        constraint = rate_limiter.version == RateLimiter::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub rate_limiter: Account<'info, RateLimiter>,
+
+    #[account(
+        seeds = [b"attestor", attestor_record.attestor.as_ref()],
+        bump = attestor_record.bump
+    )]
+    pub attestor_record: Account<'info, Attestor>,
+
+    /// CHECK: Instructions sysvar for Ed25519 signature verification
+    #[account(address = INSTRUCTIONS_ID)]
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeVerification<'info> {
+    #[account(
+        mut,
+        seeds = [b"rate_limit", rate_limiter.entity.as_ref()],
+        bump = rate_limiter.bump,
+        constraint = rate_limiter.version == RateLimiter::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub rate_limiter: Account<'info, RateLimiter>,
+
+    pub attestor: Signer<'info>,
+}
+
+pub(crate) fn check_rate_limit(ctx: Context<CheckRateLimit>) -> Result<()> {
+    let clock = Clock::get()?;
+    consume_rate_limit(&mut ctx.accounts.rate_limiter, clock.unix_timestamp)
+}
+
+
+pub(crate) fn init_rate_limiter(ctx: Context<InitRateLimiter>) -> Result<()> {
+    let rate_limiter = &mut ctx.accounts.rate_limiter;
+    let clock = Clock::get()?;
+
+    rate_limiter.entity = ctx.accounts.entity.key();
+    rate_limiter.verification_level = VerificationLevel::Basic;
+    rate_limiter.bump = ctx.bumps.rate_limiter;
+    rate_limiter.version = RateLimiter::CURRENT_VERSION;
+    rate_limiter.attested_level = VerificationLevel::Basic;
+    rate_limiter.attestation_expires_at = 0;
+    rate_limiter.attestor = Pubkey::default();
+    rate_limiter.attestation_nonce = 0;
+
+    // Start with full buckets
+    let (hour_limit, day_limit, dispute_day_limit) = get_rate_limits(VerificationLevel::Basic);
+    rate_limiter.hour_tokens = hour_limit as u64 * RATE_LIMIT_HOUR as u64;
+    rate_limiter.day_tokens = day_limit as u64 * RATE_LIMIT_DAY as u64;
+    rate_limiter.dispute_tokens = dispute_day_limit as u64 * RATE_LIMIT_DAY as u64;
+    rate_limiter.tokens_refilled_at = clock.unix_timestamp;
+
+    msg!("Rate limiter initialized for {}", ctx.accounts.entity.key());
+
+    Ok(())
+}
+
+
+pub(crate) fn add_attestor(ctx: Context<AddAttestor>, max_level: VerificationLevel) -> Result<()> {
+    require!(
+        max_level >= VerificationLevel::Social,
+        EscrowError::InvalidVerificationLevel
+    );
+
+    let attestor_record = &mut ctx.accounts.attestor_record;
+    attestor_record.attestor = ctx.accounts.attestor.key();
+    attestor_record.max_level = max_level;
+    attestor_record.added_at = Clock::get()?.unix_timestamp;
+    attestor_record.bump = ctx.bumps.attestor_record;
+    attestor_record.version = Attestor::CURRENT_VERSION;
+
+    msg!("Attestor approved: {}", attestor_record.attestor);
+
+    Ok(())
+}
+
+
+pub(crate) fn remove_attestor(ctx: Context<RemoveAttestor>) -> Result<()> {
+    msg!("Attestor removed: {}", ctx.accounts.attestor_record.attestor);
+
+    Ok(())
+}
+
+
+pub(crate) fn attest_verification(
+    ctx: Context<AttestVerification>,
+    level: VerificationLevel,
+    expires_at: i64,
+    signature: [u8; 64],
+) -> Result<()> {
+    let attestor_record = &ctx.accounts.attestor_record;
+    let clock = Clock::get()?;
+
+    require!(
+        level >= VerificationLevel::Social && level <= attestor_record.max_level,
+        EscrowError::InvalidVerificationLevel
+    );
+    require!(
+        expires_at > clock.unix_timestamp
+            && expires_at <= clock.unix_timestamp + MAX_ATTESTATION_DURATION,
+        EscrowError::InvalidAttestationExpiry
+    );
+
+    let rate_limiter = &mut ctx.accounts.rate_limiter;
+    let message = attestation_message(rate_limiter, level, expires_at);
+
+    verify_ed25519_signature(
+        &ctx.accounts.instructions_sysvar,
+        &signature,
+        &attestor_record.attestor,
+        message.as_bytes(),
+    )?;
+
+    let old_level = effective_verification_level(rate_limiter, clock.unix_timestamp);
+
+    rate_limiter.attested_level = level;
+    rate_limiter.attestation_expires_at = expires_at;
+    rate_limiter.attestor = attestor_record.attestor;
+    bump_attestation_nonce(rate_limiter)?;
+
+    msg!("Verification attested for {} until {}", rate_limiter.entity, expires_at);
+
+    emit!(VerificationAttested {
+        entity: rate_limiter.entity,
+        attestor: attestor_record.attestor,
+        level,
+        expires_at,
+    });
+
+    let new_level = effective_verification_level(rate_limiter, clock.unix_timestamp);
+    if new_level != old_level {
+        emit!(VerificationLevelChanged {
+            entity: rate_limiter.entity,
+            old_level,
+            new_level,
+            timestamp: clock.unix_timestamp,
+        });
+    }
+
+    Ok(())
+}
+
+
+pub(crate) fn revoke_verification(ctx: Context<RevokeVerification>) -> Result<()> {
+    let rate_limiter = &mut ctx.accounts.rate_limiter;
+    let clock = Clock::get()?;
+
+    require!(
+        rate_limiter.attestor == ctx.accounts.attestor.key(),
+        EscrowError::Unauthorized
+    );
+
+    let old_level = effective_verification_level(rate_limiter, clock.unix_timestamp);
+
+    rate_limiter.attested_level = VerificationLevel::Basic;
+    rate_limiter.attestation_expires_at = 0;
+    rate_limiter.attestor = Pubkey::default();
+    bump_attestation_nonce(rate_limiter)?;
+
+    let new_level = effective_verification_level(rate_limiter, clock.unix_timestamp);
+
+    msg!("Verification revoked for {}", rate_limiter.entity);
+
+    emit!(VerificationRevoked {
+        entity: rate_limiter.entity,
+        attestor: ctx.accounts.attestor.key(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    if new_level != old_level {
+        emit!(VerificationLevelChanged {
+            entity: rate_limiter.entity,
+            old_level,
+            new_level,
+            timestamp: clock.unix_timestamp,
+        });
+    }
+
+    Ok(())
+}
+
+
+/// Top up a token bucket for `elapsed` seconds
+///
+/// Buckets are stored in token-seconds: one token is worth `window` units,
+/// capacity is `limit * window`, and each second adds `limit` units. This
+/// keeps refills exact however often they run.
+pub(crate) fn refill_bucket(tokens: u64, limit: u16, elapsed: i64, window: i64) -> u64 {
+    let capacity = limit as u64 * window as u64;
+    let refill = (elapsed.max(0) as u64).saturating_mul(limit as u64);
+    tokens.saturating_add(refill).min(capacity)
+}
+
+/// Refill all of an entity's buckets at its current verification level
+///
+/// Token buckets replace the fixed hour/day windows, which let an entity
+/// spend a full allowance either side of a window boundary. A bucket never
+/// holds more than one window's allowance and refills continuously.
+pub(crate) fn refill_rate_limit_buckets(rate_limiter: &mut RateLimiter, now: i64) {
+    let (hour_limit, day_limit, dispute_day_limit) =
+        get_rate_limits(effective_verification_level(rate_limiter, now));
+    let elapsed = now - rate_limiter.tokens_refilled_at;
+
+    rate_limiter.hour_tokens =
+        refill_bucket(rate_limiter.hour_tokens, hour_limit, elapsed, RATE_LIMIT_HOUR);
+    rate_limiter.day_tokens =
+        refill_bucket(rate_limiter.day_tokens, day_limit, elapsed, RATE_LIMIT_DAY);
+    rate_limiter.dispute_tokens =
+        refill_bucket(rate_limiter.dispute_tokens, dispute_day_limit, elapsed, RATE_LIMIT_DAY);
+    rate_limiter.tokens_refilled_at = now;
+}
+
+/// Count one transaction against the entity's hourly and daily limits
+pub(crate) fn consume_rate_limit(rate_limiter: &mut RateLimiter, now: i64) -> Result<()> {
+    refill_rate_limit_buckets(rate_limiter, now);
+
+    require!(
+        rate_limiter.hour_tokens >= RATE_LIMIT_HOUR as u64,
+        EscrowError::RateLimitExceeded
+    );
+    require!(
+        rate_limiter.day_tokens >= RATE_LIMIT_DAY as u64,
+        EscrowError::RateLimitExceeded
+    );
+
+    rate_limiter.hour_tokens -= RATE_LIMIT_HOUR as u64;
+    rate_limiter.day_tokens -= RATE_LIMIT_DAY as u64;
+
+    Ok(())
+}
+
+/// Count one dispute against the entity's daily dispute cap
+///
+/// Returns the disputes still available and the cap that applied, for
+/// event reporting.
+pub(crate) fn consume_dispute_limit(rate_limiter: &mut RateLimiter, now: i64) -> Result<(u16, u16)> {
+    refill_rate_limit_buckets(rate_limiter, now);
+
+    require!(
+        rate_limiter.dispute_tokens >= RATE_LIMIT_DAY as u64,
+        EscrowError::DisputeRateLimitExceeded
+    );
+
+    rate_limiter.dispute_tokens -= RATE_LIMIT_DAY as u64;
+
+    let (_hour_limit, _day_limit, dispute_day_limit) =
+        get_rate_limits(effective_verification_level(rate_limiter, now));
+    let disputes_remaining = (rate_limiter.dispute_tokens / RATE_LIMIT_DAY as u64) as u16;
+
+    Ok((disputes_remaining, dispute_day_limit))
+}
+
+/// Level used for rate limiting: the higher of the stake-backed level and
+/// any unexpired attestation
+pub(crate) fn effective_verification_level(rate_limiter: &RateLimiter, now: i64) -> VerificationLevel {
+    if now < rate_limiter.attestation_expires_at {
+        rate_limiter.verification_level.max(rate_limiter.attested_level)
+    } else {
+        rate_limiter.verification_level
+    }
+}
+
+/// Message an attestor signs to grant `level` until `expires_at`
+pub(crate) fn attestation_message(rate_limiter: &RateLimiter, level: VerificationLevel, expires_at: i64) -> String {
+    format!(
+        "attest:{}:{}:{}:{}",
+        rate_limiter.entity, level as u8, expires_at, rate_limiter.attestation_nonce
+    )
+}
+
+/// Invalidate every attestation signed against the current nonce
+pub(crate) fn bump_attestation_nonce(rate_limiter: &mut RateLimiter) -> Result<()> {
+    rate_limiter.attestation_nonce = rate_limiter
+        .attestation_nonce
+        .checked_add(1)
+        .ok_or(EscrowError::ArithmeticOverflow)?;
+    Ok(())
+}
+
+pub(crate) fn get_rate_limits(verification: VerificationLevel) -> (u16, u16, u16) {
+    match verification {
+        VerificationLevel::Basic => (1, 10, 3),        // 1/hour, 10/day, 3 disputes/day
+        VerificationLevel::Staked => (10, 100, 10),    // 10/hour, 100/day, 10 disputes/day
+        VerificationLevel::Social => (50, 500, 50),    // 50/hour, 500/day, 50 disputes/day
+        VerificationLevel::KYC => (1000, 10000, 1000), // Unlimited
+    }
+}
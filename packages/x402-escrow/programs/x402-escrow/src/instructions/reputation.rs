@@ -0,0 +1,296 @@
+//! Entity reputation and agent/API relationship history
+
+use anchor_lang::prelude::*;
+
+use crate::*;
+
+#[derive(Accounts)]
+pub struct InitReputation<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + EntityReputation::INIT_SPACE,
+        seeds = [b"reputation", entity.key().as_ref()],
+        bump
+    )]
+    pub reputation: Account<'info, EntityReputation>,
+
+    /// CHECK: Entity being tracked
+    pub entity: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetMinCounterpartyReputation<'info> {
+    #[account(
+        mut,
+        seeds = [b"reputation", entity.key().as_ref()],
+        bump = reputation.bump,
+        constraint = reputation.version == EntityReputation::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub reputation: Account<'info, EntityReputation>,
+
+    pub entity: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitRelationship<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Relationship::INIT_SPACE,
+        seeds = [b"relationship", agent.key().as_ref(), api.key().as_ref()],
+        bump
+    )]
+    pub relationship: Account<'info, Relationship>,
+
+    /// CHECK: Agent wallet address
+    pub agent: AccountInfo<'info>,
+
+    /// CHECK: API wallet address
+    pub api: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateReputation<'info> {
+    #[account(
+        mut,
+        seeds = [b"reputation", reputation.entity.as_ref()],
+        bump = reputation.bump,
+        constraint = reputation.version == EntityReputation::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub reputation: Account<'info, EntityReputation>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = authority @ EscrowError::Unauthorized
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    pub authority: Signer<'info>,
+}
+
+pub(crate) fn init_reputation(ctx: Context<InitReputation>) -> Result<()> {
+    let reputation = &mut ctx.accounts.reputation;
+    let clock = Clock::get()?;
+
+    reputation.entity = ctx.accounts.entity.key();
+    reputation.entity_type = EntityType::Agent;
+    reputation.total_transactions = 0;
+    reputation.disputes_filed = 0;
+    reputation.disputes_won = 0;
+    reputation.disputes_partial = 0;
+    reputation.disputes_lost = 0;
+    reputation.average_quality_received = 0;
+    reputation.quality_histogram = [0; QUALITY_BUCKETS];
+    reputation.quality_sum = 0;
+    reputation.quality_samples = 0;
+    reputation.window_started_at = clock.unix_timestamp;
+    reputation.window_transactions = 0;
+    reputation.window_disputes = 0;
+    reputation.prev_window_transactions = 0;
+    reputation.prev_window_disputes = 0;
+    reputation.reputation_score = 500; // Start at medium
+    reputation.min_counterparty_score = 0;
+    reputation.created_at = clock.unix_timestamp;
+    reputation.last_updated = clock.unix_timestamp;
+    reputation.bump = ctx.bumps.reputation;
+    reputation.version = EntityReputation::CURRENT_VERSION;
+
+    msg!("Reputation initialized for {}", ctx.accounts.entity.key());
+
+    Ok(())
+}
+
+
+pub(crate) fn set_min_counterparty_reputation(
+    ctx: Context<SetMinCounterpartyReputation>,
+    min_score: u16,
+) -> Result<()> {
+    require!(min_score <= 1000, EscrowError::InvalidReputationThreshold);
+
+    let reputation = &mut ctx.accounts.reputation;
+    reputation.min_counterparty_score = min_score;
+    reputation.last_updated = Clock::get()?.unix_timestamp;
+
+    msg!("Minimum counterparty reputation set to {}", min_score);
+
+    Ok(())
+}
+
+
+pub(crate) fn init_relationship(ctx: Context<InitRelationship>) -> Result<()> {
+    let relationship = &mut ctx.accounts.relationship;
+
+    relationship.agent = ctx.accounts.agent.key();
+    relationship.api = ctx.accounts.api.key();
+    relationship.total_transactions = 0;
+    relationship.total_volume = 0;
+    relationship.disputes = 0;
+    relationship.quality_sum = 0;
+    relationship.first_transaction_at = 0;
+    relationship.last_transaction_at = 0;
+    relationship.bump = ctx.bumps.relationship;
+    relationship.version = Relationship::CURRENT_VERSION;
+    relationship.open_escrows = 0;
+    relationship.locked_value = 0;
+    relationship.hour_tokens = 0;
+    relationship.tokens_refilled_at = 0;
+
+    msg!(
+        "Relationship initialized: agent {} / API {}",
+        relationship.agent,
+        relationship.api
+    );
+
+    Ok(())
+}
+
+
+pub(crate) fn update_reputation(
+    ctx: Context<UpdateReputation>,
+    quality_score: u8,
+    refund_percentage: u8,
+) -> Result<()> {
+    let reputation = &mut ctx.accounts.reputation;
+    let clock = Clock::get()?;
+
+    reputation.total_transactions = reputation.total_transactions.saturating_add(1);
+
+    // Record quality received
+    record_quality_sample(reputation, quality_score);
+
+    // Categorize dispute outcome
+    if refund_percentage >= 75 {
+        reputation.disputes_won = reputation.disputes_won.saturating_add(1);
+    } else if refund_percentage >= 25 {
+        reputation.disputes_partial = reputation.disputes_partial.saturating_add(1);
+    } else {
+        reputation.disputes_lost = reputation.disputes_lost.saturating_add(1);
+    }
+
+    // Calculate new reputation score (0-1000)
+    reputation.reputation_score = calculate_reputation_score(reputation);
+    reputation.last_updated = clock.unix_timestamp;
+
+    msg!("Reputation updated: score = {}", reputation.reputation_score);
+
+    Ok(())
+}
+
+
+/// Decile bucket for a 0-100 quality score (100 shares the top bucket)
+pub(crate) fn quality_bucket(quality_score: u8) -> usize {
+    (quality_score as usize / 10).min(QUALITY_BUCKETS - 1)
+}
+
+/// Add one quality observation to the histogram and exact running sum
+///
+/// `average_quality_received` is derived from the sum rather than updated
+/// incrementally, so rounding error does not accumulate.
+pub(crate) fn record_quality_sample(reputation: &mut EntityReputation, quality_score: u8) {
+    let bucket = quality_bucket(quality_score);
+    reputation.quality_histogram[bucket] = reputation.quality_histogram[bucket].saturating_add(1);
+    reputation.quality_sum = reputation.quality_sum.saturating_add(quality_score as u64);
+    reputation.quality_samples = reputation.quality_samples.saturating_add(1);
+    reputation.average_quality_received = quality_mean(reputation).unwrap_or(0);
+}
+
+/// Mean quality score rounded to nearest, from the exact running sum
+pub(crate) fn quality_mean(reputation: &EntityReputation) -> Option<u8> {
+    if reputation.quality_samples == 0 {
+        return None;
+    }
+
+    let mean = (reputation.quality_sum + reputation.quality_samples / 2) / reputation.quality_samples;
+    Some(mean.min(100) as u8)
+}
+
+/// Lower edge of the decile containing the `percentile`-th observation
+pub(crate) fn quality_percentile(reputation: &EntityReputation, percentile: u8) -> Option<u8> {
+    if reputation.quality_samples == 0 {
+        return None;
+    }
+
+    // Rank of the target observation (1-based, nearest-rank method)
+    let rank = (reputation.quality_samples * percentile as u64).div_ceil(100).max(1);
+
+    let mut seen = 0u64;
+    for (bucket, count) in reputation.quality_histogram.iter().enumerate() {
+        seen += *count as u64;
+        if seen >= rank {
+            return Some((bucket * 10) as u8);
+        }
+    }
+
+    // Histogram saturated below the sample count; report the top bucket
+    Some(((QUALITY_BUCKETS - 1) * 10) as u8)
+}
+
+pub(crate) fn calculate_reputation_score(reputation: &EntityReputation) -> u16 {
+    if reputation.total_transactions == 0 {
+        return 500; // Default medium score
+    }
+
+    let tx_score = reputation.total_transactions.min(100) as u16 * 5; // Max 500 from transactions
+
+    let dispute_score = if reputation.disputes_filed > 0 {
+        let win_rate = (reputation.disputes_won * 100) / reputation.disputes_filed;
+        (win_rate as u16 * 3).min(300) // Max 300 from dispute wins
+    } else {
+        150 // No disputes, neutral
+    };
+
+    let quality_score = (reputation.average_quality_received as u16 * 2).min(200); // Max 200 from quality
+
+    (tx_score + dispute_score + quality_score).min(1000)
+}
+
+/// Record a settled escrow on the agent/API relationship
+///
+/// `quality_score` is only known when the escrow went through dispute
+/// resolution; releases without a dispute count towards volume only.
+pub(crate) fn record_relationship_settlement(
+    relationship: &mut Relationship,
+    amount: u64,
+    quality_score: Option<u8>,
+    now: i64,
+) {
+    relationship.total_transactions = relationship.total_transactions.saturating_add(1);
+    relationship.total_volume = relationship.total_volume.saturating_add(amount);
+
+    if let Some(quality_score) = quality_score {
+        relationship.disputes = relationship.disputes.saturating_add(1);
+        relationship.quality_sum = relationship.quality_sum.saturating_add(quality_score as u64);
+    }
+
+    if relationship.first_transaction_at == 0 {
+        relationship.first_transaction_at = now;
+    }
+    relationship.last_transaction_at = now;
+
+    // Escrows opened before relationships were tracked were never counted,
+    // hence saturating
+    relationship.open_escrows = relationship.open_escrows.saturating_sub(1);
+    relationship.locked_value = relationship.locked_value.saturating_sub(amount);
+}
+
+/// Mean dispute quality between the pair, rounded to nearest
+pub(crate) fn relationship_average_quality(relationship: &Relationship) -> Option<u8> {
+    if relationship.disputes == 0 {
+        return None;
+    }
+
+    let mean = (relationship.quality_sum + relationship.disputes / 2) / relationship.disputes;
+    Some(mean.min(100) as u8)
+}
@@ -0,0 +1,256 @@
+//! Staking towards `VerificationLevel::Staked`
+
+use anchor_lang::prelude::*;
+
+use crate::*;
+
+#[derive(Accounts)]
+pub struct InitStakeVault<'info> {
+    #[account(
+        init,
+        payer = entity,
+        space = 8 + StakeVault::INIT_SPACE,
+        seeds = [b"stake", entity.key().as_ref()],
+        bump
+    )]
+    pub stake_vault: Account<'info, StakeVault>,
+
+    #[account(mut)]
+    pub entity: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Stake<'info> {
+    #[account(
+        mut,
+        seeds = [b"stake", entity.key().as_ref()],
+        bump = stake_vault.bump,
+        constraint = stake_vault.version == StakeVault::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub stake_vault: Account<'info, StakeVault>,
+
+    #[account(
+        mut,
+        seeds = [b"rate_limit", entity.key().as_ref()],
+        bump = rate_limiter.bump,
+        constraint = rate_limiter.version == RateLimiter::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub rate_limiter: Account<'info, RateLimiter>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(mut)]
+    pub entity: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RequestUnstake<'info> {
+    #[account(
+        mut,
+        seeds = [b"stake", entity.key().as_ref()],
+        bump = stake_vault.bump,
+        constraint = stake_vault.version == StakeVault::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub stake_vault: Account<'info, StakeVault>,
+
+    #[account(
+        mut,
+        seeds = [b"rate_limit", entity.key().as_ref()],
+        bump = rate_limiter.bump,
+        constraint = rate_limiter.version == RateLimiter::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub rate_limiter: Account<'info, RateLimiter>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    pub entity: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawUnstaked<'info> {
+    #[account(
+        mut,
+        seeds = [b"stake", entity.key().as_ref()],
+        bump = stake_vault.bump,
+        constraint = stake_vault.version == StakeVault::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub stake_vault: Account<'info, StakeVault>,
+
+    #[account(
+        seeds = [b"rate_limit", entity.key().as_ref()],
+        bump = rate_limiter.bump
+    )]
+    pub rate_limiter: Account<'info, RateLimiter>,
+
+    #[account(mut)]
+    pub entity: Signer<'info>,
+}
+
+pub(crate) fn init_stake_vault(ctx: Context<InitStakeVault>) -> Result<()> {
+    let stake_vault = &mut ctx.accounts.stake_vault;
+
+    stake_vault.entity = ctx.accounts.entity.key();
+    stake_vault.staked_amount = 0;
+    stake_vault.unbonding_amount = 0;
+    stake_vault.unbonding_ends_at = 0;
+    stake_vault.bump = ctx.bumps.stake_vault;
+    stake_vault.version = StakeVault::CURRENT_VERSION;
+
+    msg!("Stake vault initialized for {}", ctx.accounts.entity.key());
+
+    Ok(())
+}
+
+
+pub(crate) fn stake(ctx: Context<Stake>, amount: u64) -> Result<()> {
+    require!(amount > 0, EscrowError::InvalidAmount);
+
+    let cpi_context = CpiContext::new(
+        ctx.accounts.system_program.to_account_info(),
+        anchor_lang::system_program::Transfer {
+            from: ctx.accounts.entity.to_account_info(),
+            to: ctx.accounts.stake_vault.to_account_info(),
+        },
+    );
+    anchor_lang::system_program::transfer(cpi_context, amount)?;
+
+    let stake_vault = &mut ctx.accounts.stake_vault;
+    stake_vault.staked_amount = stake_vault
+        .staked_amount
+        .checked_add(amount)
+        .ok_or(EscrowError::ArithmeticOverflow)?;
+
+    let clock = Clock::get()?;
+    apply_stake_level(
+        &mut ctx.accounts.rate_limiter,
+        stake_vault.staked_amount,
+        ctx.accounts.config.staked_level_threshold,
+        clock.unix_timestamp,
+    );
+
+    msg!("Staked {} lamports (total {})", amount, stake_vault.staked_amount);
+
+    emit!(StakeUpdated {
+        entity: stake_vault.entity,
+        staked_amount: stake_vault.staked_amount,
+        unbonding_amount: stake_vault.unbonding_amount,
+        unbonding_ends_at: stake_vault.unbonding_ends_at,
+        verification_level: ctx.accounts.rate_limiter.verification_level,
+    });
+
+    Ok(())
+}
+
+
+pub(crate) fn request_unstake(ctx: Context<RequestUnstake>, amount: u64) -> Result<()> {
+    let stake_vault = &mut ctx.accounts.stake_vault;
+    let clock = Clock::get()?;
+
+    require!(amount > 0, EscrowError::InvalidAmount);
+    require!(
+        amount <= stake_vault.staked_amount,
+        EscrowError::InsufficientStake
+    );
+
+    stake_vault.staked_amount -= amount;
+    stake_vault.unbonding_amount = stake_vault
+        .unbonding_amount
+        .checked_add(amount)
+        .ok_or(EscrowError::ArithmeticOverflow)?;
+    stake_vault.unbonding_ends_at = clock.unix_timestamp + UNSTAKE_UNBONDING_PERIOD;
+
+    apply_stake_level(
+        &mut ctx.accounts.rate_limiter,
+        stake_vault.staked_amount,
+        ctx.accounts.config.staked_level_threshold,
+        clock.unix_timestamp,
+    );
+
+    msg!(
+        "Unbonding {} lamports until {}",
+        stake_vault.unbonding_amount,
+        stake_vault.unbonding_ends_at
+    );
+
+    emit!(StakeUpdated {
+        entity: stake_vault.entity,
+        staked_amount: stake_vault.staked_amount,
+        unbonding_amount: stake_vault.unbonding_amount,
+        unbonding_ends_at: stake_vault.unbonding_ends_at,
+        verification_level: ctx.accounts.rate_limiter.verification_level,
+    });
+
+    Ok(())
+}
+
+
+pub(crate) fn withdraw_unstaked(ctx: Context<WithdrawUnstaked>) -> Result<()> {
+    let clock = Clock::get()?;
+    let stake_vault = &ctx.accounts.stake_vault;
+
+    let amount = stake_vault.unbonding_amount;
+    require!(amount > 0, EscrowError::InsufficientStake);
+    require!(
+        clock.unix_timestamp >= stake_vault.unbonding_ends_at,
+        EscrowError::UnbondingNotComplete
+    );
+
+    // Vault PDA holds data, so move lamports directly, never touching
+    // its rent reserve
+    let stake_vault_info = stake_vault.to_account_info();
+    let reserve = Rent::get()?.minimum_balance(stake_vault_info.data_len());
+    require!(
+        stake_vault_info.lamports().saturating_sub(reserve) >= amount,
+        EscrowError::InsufficientRentReserve
+    );
+    transfer_lamports(&stake_vault_info, &ctx.accounts.entity.to_account_info(), amount)?;
+
+    let stake_vault = &mut ctx.accounts.stake_vault;
+    stake_vault.unbonding_amount = 0;
+    stake_vault.unbonding_ends_at = 0;
+
+    msg!("Withdrew {} lamports of unbonded stake", amount);
+
+    emit!(StakeUpdated {
+        entity: stake_vault.entity,
+        staked_amount: stake_vault.staked_amount,
+        unbonding_amount: 0,
+        unbonding_ends_at: 0,
+        verification_level: ctx.accounts.rate_limiter.verification_level,
+    });
+
+    Ok(())
+}
+
+
+/// Move an entity between `Basic` and `Staked` as its active stake crosses
+/// `threshold`
+///
+/// Higher levels are not granted by stake and are left untouched.
+pub(crate) fn apply_stake_level(rate_limiter: &mut RateLimiter, staked_amount: u64, threshold: u64, now: i64) {
+    let old_level = rate_limiter.verification_level;
+    let new_level = match old_level {
+        VerificationLevel::Basic if staked_amount >= threshold => VerificationLevel::Staked,
+        VerificationLevel::Staked if staked_amount < threshold => VerificationLevel::Basic,
+        level => level,
+    };
+
+    if new_level != old_level {
+        rate_limiter.verification_level = new_level;
+
+        msg!("Verification level changed for {}", rate_limiter.entity);
+
+        emit!(VerificationLevelChanged {
+            entity: rate_limiter.entity,
+            old_level,
+            new_level,
+            timestamp: now,
+        });
+    }
+}
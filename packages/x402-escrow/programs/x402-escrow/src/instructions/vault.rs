@@ -0,0 +1,251 @@
+//! Agent vaults and the session keys that spend from them
+
+use anchor_lang::prelude::*;
+
+use crate::*;
+
+#[derive(Accounts)]
+pub struct InitAgentVault<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + AgentVault::INIT_SPACE,
+        seeds = [b"vault", owner.key().as_ref()],
+        bump
+    )]
+    pub agent_vault: Account<'info, AgentVault>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateAgentVault<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", owner.key().as_ref()],
+        bump = agent_vault.bump,
+        has_one = owner @ EscrowError::Unauthorized,
+        constraint = agent_vault.version == AgentVault::CURRENT_VERSION @ EscrowError::AccountNotMigrated
+    )]
+    pub agent_vault: Account<'info, AgentVault>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(session_key: Pubkey)]
+pub struct CreateSessionKey<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + SessionKey::INIT_SPACE,
+        seeds = [b"session", owner.key().as_ref(), session_key.as_ref()],
+        bump
+    )]
+    pub session: Account<'info, SessionKey>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeSessionKey<'info> {
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"session", owner.key().as_ref(), session.session_key.as_ref()],
+        bump = session.bump,
+        has_one = owner @ EscrowError::Unauthorized
+    )]
+    pub session: Account<'info, SessionKey>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+pub(crate) fn init_agent_vault(ctx: Context<InitAgentVault>) -> Result<()> {
+    let agent_vault = &mut ctx.accounts.agent_vault;
+
+    agent_vault.owner = ctx.accounts.owner.key();
+    agent_vault.bump = ctx.bumps.agent_vault;
+    agent_vault.version = AgentVault::CURRENT_VERSION;
+
+    msg!("Vault opened for agent {}", agent_vault.owner);
+
+    Ok(())
+}
+
+
+pub(crate) fn deposit_to_vault(ctx: Context<UpdateAgentVault>, amount: u64) -> Result<()> {
+    require!(amount > 0, EscrowError::InvalidAmount);
+
+    let cpi_context = CpiContext::new(
+        ctx.accounts.system_program.to_account_info(),
+        anchor_lang::system_program::Transfer {
+            from: ctx.accounts.owner.to_account_info(),
+            to: ctx.accounts.agent_vault.to_account_info(),
+        },
+    );
+    anchor_lang::system_program::transfer(cpi_context, amount)?;
+
+    emit!(AgentVaultUpdated {
+        owner: ctx.accounts.owner.key(),
+        available: agent_vault_available(&ctx.accounts.agent_vault.to_account_info())?,
+    });
+
+    Ok(())
+}
+
+
+pub(crate) fn withdraw_from_vault(ctx: Context<UpdateAgentVault>, amount: u64) -> Result<()> {
+    require!(amount > 0, EscrowError::InvalidAmount);
+
+    let vault_info = ctx.accounts.agent_vault.to_account_info();
+    withdraw_from_agent_vault(&vault_info, &ctx.accounts.owner.to_account_info(), amount)?;
+
+    emit!(AgentVaultUpdated {
+        owner: ctx.accounts.owner.key(),
+        available: agent_vault_available(&vault_info)?,
+    });
+
+    Ok(())
+}
+
+
+pub(crate) fn create_session_key(
+    ctx: Context<CreateSessionKey>,
+    session_key: Pubkey,
+    expires_at: i64,
+    max_per_escrow: u64,
+    spend_cap: u64,
+    allowed_providers: Vec<Pubkey>,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    require!(
+        expires_at > clock.unix_timestamp
+            && max_per_escrow > 0
+            && max_per_escrow <= spend_cap
+            && allowed_providers.len() <= MAX_SESSION_PROVIDERS,
+        EscrowError::InvalidSessionKey
+    );
+
+    let mut provider_slots = [Pubkey::default(); MAX_SESSION_PROVIDERS];
+    provider_slots[..allowed_providers.len()].copy_from_slice(&allowed_providers);
+
+    let session = &mut ctx.accounts.session;
+    session.owner = ctx.accounts.owner.key();
+    session.session_key = session_key;
+    session.expires_at = expires_at;
+    session.max_per_escrow = max_per_escrow;
+    session.spend_cap = spend_cap;
+    session.spent = 0;
+    session.allowed_providers = provider_slots;
+    session.created_at = clock.unix_timestamp;
+    session.bump = ctx.bumps.session;
+    session.version = SessionKey::CURRENT_VERSION;
+
+    emit!(SessionKeyCreated {
+        owner: session.owner,
+        session_key,
+        expires_at,
+        max_per_escrow,
+        spend_cap,
+        allowed_providers,
+    });
+
+    Ok(())
+}
+
+
+pub(crate) fn revoke_session_key(ctx: Context<RevokeSessionKey>) -> Result<()> {
+    let session = &ctx.accounts.session;
+
+    msg!("Session key revoked: {}", session.session_key);
+
+    emit!(SessionKeyRevoked {
+        owner: session.owner,
+        session_key: session.session_key,
+        spent: session.spent,
+    });
+
+    Ok(())
+}
+
+
+/// Lamports in the agent's vault above its rent-exempt reserve
+pub(crate) fn agent_vault_available(agent_vault: &AccountInfo) -> Result<u64> {
+    let reserve = Rent::get()?.minimum_balance(agent_vault.data_len());
+    Ok(agent_vault.lamports().saturating_sub(reserve))
+}
+
+/// Pay `amount` out of the agent's vault, never touching its rent reserve
+pub(crate) fn withdraw_from_agent_vault(agent_vault: &AccountInfo, to: &AccountInfo, amount: u64) -> Result<()> {
+    require_keys_eq!(*agent_vault.owner, crate::ID, EscrowError::InsufficientVaultBalance);
+    require!(
+        agent_vault_available(agent_vault)? >= amount,
+        EscrowError::InsufficientVaultBalance
+    );
+
+    transfer_lamports(agent_vault, to, amount)
+}
+
+/// Authorize `authority` to spend `amount` for `agent` through a session key
+///
+/// Charges the session's spend cap. For new escrows (`api` set) also
+/// enforces the per-escrow maximum and the allowed provider list.
+pub(crate) fn charge_session(
+    session: &AccountInfo,
+    agent: &Pubkey,
+    authority: &Pubkey,
+    amount: u64,
+    api: Option<&Pubkey>,
+    now: i64,
+) -> Result<()> {
+    require_keys_eq!(*session.owner, crate::ID, EscrowError::Unauthorized);
+
+    let mut data = session.try_borrow_mut_data()?;
+    let mut session_key = SessionKey::try_deserialize(&mut &data[..])?;
+    require_current_version(&session_key)?;
+    require!(
+        session_key.owner == *agent && session_key.session_key == *authority,
+        EscrowError::Unauthorized
+    );
+    spend_session(&mut session_key, amount, api, now)?;
+    session_key.try_serialize(&mut &mut data[..])?;
+
+    Ok(())
+}
+
+/// Check `amount` against a session key's limits and count it as spent
+pub(crate) fn spend_session(session: &mut SessionKey, amount: u64, api: Option<&Pubkey>, now: i64) -> Result<()> {
+    require!(now < session.expires_at, EscrowError::SessionKeyExpired);
+
+    if let Some(api) = api {
+        require!(amount <= session.max_per_escrow, EscrowError::SessionLimitExceeded);
+        let mut allowed = session
+            .allowed_providers
+            .iter()
+            .filter(|key| **key != Pubkey::default())
+            .peekable();
+        require!(
+            allowed.peek().is_none() || allowed.any(|key| key == api),
+            EscrowError::SessionLimitExceeded
+        );
+    }
+
+    session.spent = session
+        .spent
+        .checked_add(amount)
+        .filter(|spent| *spent <= session.spend_cap)
+        .ok_or(EscrowError::SessionLimitExceeded)?;
+
+    Ok(())
+}
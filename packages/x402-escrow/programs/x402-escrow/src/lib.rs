@@ -2,13 +2,55 @@
 //!
 //! Time-locked PDA escrow with Ed25519-verified quality assessment
 //! for HTTP 402 API dispute resolution.
+//!
+//! ## CPI view interface
+//!
+//! Other programs can gate on escrow and reputation state without
+//! deserializing our accounts. Depend on this crate with the `cpi` feature
+//! and call the read-only instructions; each returns a Borsh-encoded summary
+//! through `set_return_data`:
+//!
+//! ```ignore
+//! use x402_escrow::cpi::{accounts::GetReputation, get_reputation};
+//!
+//! let ctx = CpiContext::new(
+//!     x402_program.to_account_info(),
+//!     GetReputation { reputation: agent_reputation.to_account_info() },
+//! );
+//! let summary: x402_escrow::ReputationSummary = get_reputation(ctx)?.get();
+//! require!(summary.reputation_score >= 600, MyError::Untrusted);
+//! ```
+//!
+//! | Instruction          | Returns             |
+//! |----------------------|---------------------|
+//! | `get_reputation`     | [`ReputationSummary`] |
+//! | `get_escrow_status`  | [`EscrowSummary`]     |
+//! | `quote_dispute_cost` | [`DisputeQuote`]      |
+//! | `get_relationship`   | [`RelationshipSummary`] |
+//! | `get_quality_distribution` | [`QualityDistribution`] |
+//! | `get_work_agreement` | [`WorkAgreementSummary`] |
+//!
+//! Summary layouts are stable: fields are never reordered or removed, and
+//! new data is exposed through new view instructions rather than by
+//! extending an existing return type. Accounts added to a view later are
+//! optional trailing accounts, so callers built against its original account
+//! list keep working.
 
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::{
-    ed25519_program,
-    sysvar::instructions::{load_instruction_at_checked, ID as INSTRUCTIONS_ID},
-};
-use switchboard_on_demand::on_demand::accounts::pull_feed::PullFeedAccountData;
+
+pub mod errors;
+pub mod events;
+pub mod instructions;
+pub mod state;
+pub mod views;
+mod utils;
+
+pub use errors::*;
+pub use events::*;
+pub use instructions::*;
+pub use state::*;
+pub use utils::*;
+pub use views::*;
 
 declare_id!("E5EiaJhbg6Bav1v3P211LNv1tAqa4fHVeuGgRBHsEu6n");
 
@@ -17,121 +59,39 @@ const MIN_TIME_LOCK: i64 = 3600;                    // 1 hour
 const MAX_TIME_LOCK: i64 = 2_592_000;               // 30 days
 const MAX_ESCROW_AMOUNT: u64 = 1_000_000_000_000;   // 1000 SOL
 const MIN_ESCROW_AMOUNT: u64 = 1_000_000;           // 0.001 SOL
-// Dispute window constant - currently handled per-escrow
-// const DISPUTE_WINDOW: i64 = 172_800;                // 48 hours
-const BASE_DISPUTE_COST: u64 = 1_000_000;           // 0.001 SOL
-
-#[event]
-pub struct EscrowInitialized {
-    pub escrow: Pubkey,
-    pub agent: Pubkey,
-    pub api: Pubkey,
-    pub amount: u64,
-    pub expires_at: i64,
-    pub transaction_id: String,
-}
-
-#[event]
-pub struct DisputeMarked {
-    pub escrow: Pubkey,
-    pub agent: Pubkey,
-    pub transaction_id: String,
-    pub timestamp: i64,
-}
-
-#[event]
-pub struct DisputeResolved {
-    pub escrow: Pubkey,
-    pub transaction_id: String,
-    pub quality_score: u8,
-    pub refund_percentage: u8,
-    pub refund_amount: u64,
-    pub payment_amount: u64,
-    pub verifier: Pubkey,
-}
-
-#[event]
-pub struct FundsReleased {
-    pub escrow: Pubkey,
-    pub transaction_id: String,
-    pub amount: u64,
-    pub api: Pubkey,
-    pub timestamp: i64,
-}
-
-/// Verify Ed25519 signature instruction
-///
-/// Checks that an Ed25519 signature verification instruction exists in the transaction
-/// and validates the signature against the expected message format
-pub fn verify_ed25519_signature(
-    instructions_sysvar: &AccountInfo,
-    signature: &[u8; 64],
-    verifier_pubkey: &Pubkey,
-    message: &[u8],
-) -> Result<()> {
-        // Load the Ed25519 instruction from the sysvar
-        // Expected to be at index 0 (before the current instruction)
-        let ix = load_instruction_at_checked(0, instructions_sysvar)
-            .map_err(|_| error!(EscrowError::InvalidSignature))?;
-
-        // Verify it's the Ed25519 program
-        require!(
-            ix.program_id == ed25519_program::ID,
-            EscrowError::InvalidSignature
-        );
-
-        // Ed25519 instruction data layout:
-        // [0]: num_signatures (should be 1)
-        // [1]: padding
-        // [2..4]: signature_offset (u16)
-        // [4..6]: signature_instruction_index (u16)
-        // [6..8]: public_key_offset (u16)
-        // [8..10]: public_key_instruction_index (u16)
-        // [10..12]: message_data_offset (u16)
-        // [12..14]: message_data_size (u16)
-        // [14..16]: message_instruction_index (u16)
-        // [16..]: data (signature + pubkey + message)
-
-        require!(
-            ix.data.len() >= 16,
-            EscrowError::InvalidSignature
-        );
-
-        // Verify we have exactly 1 signature
-        require!(
-            ix.data[0] == 1,
-            EscrowError::InvalidSignature
-        );
-
-        // Parse offsets
-        let sig_offset = u16::from_le_bytes([ix.data[2], ix.data[3]]) as usize;
-        let pubkey_offset = u16::from_le_bytes([ix.data[6], ix.data[7]]) as usize;
-        let message_offset = u16::from_le_bytes([ix.data[10], ix.data[11]]) as usize;
-        let message_size = u16::from_le_bytes([ix.data[12], ix.data[13]]) as usize;
-
-        // Verify signature matches
-        let ix_signature = &ix.data[sig_offset..sig_offset + 64];
-        require!(
-            ix_signature == signature,
-            EscrowError::InvalidSignature
-        );
-
-        // Verify public key matches
-        let ix_pubkey = &ix.data[pubkey_offset..pubkey_offset + 32];
-        require!(
-            ix_pubkey == verifier_pubkey.as_ref(),
-            EscrowError::InvalidSignature
-        );
-
-        // Verify message matches
-        let ix_message = &ix.data[message_offset..message_offset + message_size];
-        require!(
-            ix_message == message,
-            EscrowError::InvalidSignature
-        );
-
-        Ok(())
-}
+const DISPUTE_WINDOW: i64 = 172_800;                // 48 hours from confirmed delivery (default)
+const MIN_DISPUTE_WINDOW: i64 = 600;                // 10 minutes
+const DEFAULT_RESOLUTION_PERIOD: i64 = 604_800;     // 7 days for a verifier to resolve
+const DEFAULT_UNRESOLVED_REFUND_PERCENTAGE: u8 = 50; // Split applied by settle_unresolved
+const BASE_DISPUTE_COST: u64 = 1_000_000;           // 0.001 SOL (default, see ProgramConfig)
+const DISPUTE_COST_TIERS: usize = 4;
+const DISPUTE_RATE_WINDOW: i64 = 2_592_000;         // 30 days per rolling window bucket
+const POOR_QUALITY_THRESHOLD: u8 = 30;              // Quality below this counts against the provider
+const DEFAULT_POOR_QUALITY_PER_STRIKE: u32 = 3;
+const DEFAULT_STRIKES_TO_SUSPEND: u8 = 3;
+const DEFAULT_SUSPENSION_DURATION: i64 = 604_800;   // 7 days
+const DEFAULT_APPEAL_BOND: u64 = 100_000_000;       // 0.1 SOL
+const DEFAULT_STRIKE_DECAY_PERIOD: i64 = 2_592_000; // 30 days per strike
+const QUALITY_BUCKETS: usize = 10;                  // Deciles: 0-9, 10-19, ..., 90-100
+const DEFAULT_STAKED_LEVEL_THRESHOLD: u64 = 1_000_000_000; // 1 SOL for Staked (default, see ProgramConfig)
+const UNSTAKE_UNBONDING_PERIOD: i64 = 604_800;      // 7 days
+const MAX_ATTESTATION_DURATION: i64 = 31_536_000;   // 365 days
+const RATE_LIMIT_HOUR: i64 = 3600;                  // Hourly bucket refill period
+const RATE_LIMIT_DAY: i64 = 86_400;                 // Daily bucket refill period
+const MAX_ACCEPTED_VERIFIERS: usize = 4;            // Verifier slots in a Provider account
+const MAX_SESSION_PROVIDERS: usize = 4;             // Provider slots in a SessionKey account
+const MAX_MEMO_LEN: usize = 64;                     // Bytes in EscrowMetadata::memo
+const MAX_PENALTY_REFUND_PERCENTAGE: u8 = 200;      // Refunds above 100% come from the provider bond
+
+/// SLA refund table: (min availability in basis points, max latency as a
+/// multiple of the SLA limit, refund percentage). The first row a
+/// measurement satisfies sets the refund; anything worse refunds in full.
+const SLA_REFUND_TABLE: [(u16, u32, u8); 4] = [
+    (9_900, 1, 0),    // Within SLA
+    (9_500, 2, 25),   // Minor breach
+    (9_000, 4, 50),   // Major breach
+    (5_000, 10, 75),  // Severe breach
+];
 
 /// x402Resolve Escrow Program
 ///
@@ -145,151 +105,110 @@ pub mod x402_escrow {
     ///
     /// # Arguments
     /// * `amount` - Amount to escrow (lamports)
-    /// * `time_lock` - Duration before auto-release (seconds)
+    /// * `time_lock` - Duration before auto-release (seconds, 0 = provider's default)
     /// * `transaction_id` - Unique transaction identifier
+    /// * `metadata` - Optional record of what was bought, for reconciliation
+    /// * `fund_from_vault` - Draw from the agent's vault rather than the signer's
+    ///   wallet; session keys must set it
     pub fn initialize_escrow(
         ctx: Context<InitializeEscrow>,
         amount: u64,
         time_lock: i64,
         transaction_id: String,
+        metadata: Option<EscrowMetadata>,
+        fund_from_vault: bool,
+    ) -> Result<()> {
+        instructions::escrow::initialize_escrow(
+            ctx,
+            amount,
+            time_lock,
+            transaction_id,
+            metadata,
+            fund_from_vault,
+        )
+    }
+
+    /// Record the scope both parties agreed to for an escrow
+    ///
+    /// Call in the same transaction as `initialize_escrow` or right after,
+    /// while the escrow is still active. Both the agent and the API sign, so
+    /// the terms are ones the provider accepted. Once set, `resolve_dispute`
+    /// only accepts verifier signatures that commit to the agreement hash,
+    /// and Switchboard resolution is no longer available.
+    ///
+    /// # Arguments
+    /// * `query` - The request the API is being paid to answer
+    /// * `required_fields` - Fields every record must contain (bitmask or count)
+    /// * `min_records` - Minimum number of records in the response
+    /// * `max_age_days` - Maximum age of the returned data
+    /// * `min_quality_score` - Quality score below which a refund is due (0-100)
+    pub fn create_work_agreement(
+        ctx: Context<CreateWorkAgreement>,
+        query: String,
+        required_fields: u8,
+        min_records: u32,
+        max_age_days: u32,
+        min_quality_score: u8,
+    ) -> Result<()> {
+        instructions::escrow::create_work_agreement(
+            ctx,
+            query,
+            required_fields,
+            min_records,
+            max_age_days,
+            min_quality_score,
+        )
+    }
+
+    /// Commit a hash of the delivered response to the escrow (API only)
+    ///
+    /// Restarts the dispute window from now (see `dispute_window_end`), and
+    /// binds verifier messages to `response_hash` (see `verifier_message`).
+    /// Must happen while the escrow is unexpired and its dispute window still
+    /// open, so a late confirmation cannot reopen either.
+    ///
+    /// # Arguments
+    /// * `response_hash` - SHA-256 of the HTTP response body
+    /// * `record_count` - Number of records in the response
+    pub fn confirm_delivery(
+        ctx: Context<ConfirmDelivery>,
+        response_hash: [u8; 32],
+        record_count: u32,
     ) -> Result<()> {
-        // Validate inputs
-        require!(
-            amount >= MIN_ESCROW_AMOUNT,
-            EscrowError::InvalidAmount
-        );
-        require!(
-            amount <= MAX_ESCROW_AMOUNT,
-            EscrowError::AmountTooLarge
-        );
-        require!(
-            time_lock >= MIN_TIME_LOCK && time_lock <= MAX_TIME_LOCK,
-            EscrowError::InvalidTimeLock
-        );
-        require!(
-            !transaction_id.is_empty() && transaction_id.len() <= 64,
-            EscrowError::InvalidTransactionId
-        );
-
-        let clock = Clock::get()?;
-
-        // Initialize escrow state
-        {
-            let escrow = &mut ctx.accounts.escrow;
-            escrow.agent = ctx.accounts.agent.key();
-            escrow.api = ctx.accounts.api.key();
-            escrow.amount = amount;
-            escrow.status = EscrowStatus::Active;
-            escrow.created_at = clock.unix_timestamp;
-            escrow.expires_at = clock.unix_timestamp + time_lock;
-            escrow.transaction_id = transaction_id.clone();
-            escrow.bump = ctx.bumps.escrow;
-        }
-
-        // Verify transfer amount covers rent before executing
-        let rent = Rent::get()?;
-        let min_rent = rent.minimum_balance(8 + Escrow::INIT_SPACE);
-        require!(
-            amount >= min_rent,
-            EscrowError::InsufficientRentReserve
-        );
-
-        // Transfer SOL to escrow PDA
-        let cpi_context = CpiContext::new(
-            ctx.accounts.system_program.to_account_info(),
-            anchor_lang::system_program::Transfer {
-                from: ctx.accounts.agent.to_account_info(),
-                to: ctx.accounts.escrow.to_account_info(),
-            },
-        );
-        anchor_lang::system_program::transfer(cpi_context, amount)?;
-
-        let expires_at = clock.unix_timestamp + time_lock;
-        msg!("Escrow initialized: {} SOL locked", amount as f64 / 1_000_000_000.0);
-        msg!("Expires at: {}", expires_at);
-
-        let escrow = &ctx.accounts.escrow;
-        emit!(EscrowInitialized {
-            escrow: escrow.key(),
-            agent: escrow.agent,
-            api: escrow.api,
-            amount: escrow.amount,
-            expires_at: escrow.expires_at,
-            transaction_id: transaction_id,
-        });
-
-        Ok(())
+        instructions::escrow::confirm_delivery(ctx, response_hash, record_count)
+    }
+
+    /// Attach SLA terms to an escrow before delivery (agent and API co-sign)
+    ///
+    /// Enables `settle_sla`, which refunds from `SLA_REFUND_TABLE` on signed
+    /// measurements instead of a quality assessment.
+    ///
+    /// # Arguments
+    /// * `max_latency_ms` - Latency limit for the response (0 = none)
+    /// * `deadline` - Future unix timestamp by which delivery must be confirmed (0 = none)
+    pub fn set_escrow_sla(
+        ctx: Context<SetEscrowSla>,
+        max_latency_ms: u32,
+        deadline: i64,
+    ) -> Result<()> {
+        instructions::escrow::set_escrow_sla(ctx, max_latency_ms, deadline)
     }
 
     /// Release funds to API (happy path - no dispute)
     ///
     /// Can be called by:
     /// - Agent (explicitly releasing)
-    /// - Anyone after time_lock expires (auto-release)
+    /// - Anyone once the time lock has expired and the dispute window has
+    ///   closed (auto-release, see `auto_release_at`)
     pub fn release_funds(ctx: Context<ReleaseFunds>) -> Result<()> {
-        let escrow = &mut ctx.accounts.escrow;
-        let clock = Clock::get()?;
-
-        require!(
-            escrow.status == EscrowStatus::Active,
-            EscrowError::InvalidStatus
-        );
-
-        // Check if caller is agent OR time_lock expired
-        let is_agent = ctx.accounts.agent.key() == escrow.agent;
-        let time_lock_expired = clock.unix_timestamp >= escrow.expires_at;
-
-        // If not agent, time lock must have expired
-        if !is_agent {
-            require!(time_lock_expired, EscrowError::TimeLockNotExpired);
-        }
-
-        require!(is_agent || time_lock_expired, EscrowError::Unauthorized);
-
-        // Copy values before PDA signing
-        let transfer_amount = escrow.amount;
-        let transaction_id = escrow.transaction_id.clone();
-        let bump = escrow.bump;
-
-        // Transfer full amount to API
-        let seeds = &[
-            b"escrow",
-            transaction_id.as_bytes(),
-            &[bump],
-        ];
-        let signer = &[&seeds[..]];
-
-        let cpi_context = CpiContext::new_with_signer(
-            ctx.accounts.system_program.to_account_info(),
-            anchor_lang::system_program::Transfer {
-                from: ctx.accounts.escrow.to_account_info(),
-                to: ctx.accounts.api.to_account_info(),
-            },
-            signer,
-        );
-        anchor_lang::system_program::transfer(cpi_context, transfer_amount)?;
-
-        let escrow = &mut ctx.accounts.escrow;
-        escrow.status = EscrowStatus::Released;
-
-        msg!("Funds released to API: {} SOL", escrow.amount as f64 / 1_000_000_000.0);
-
-        let clock = Clock::get()?;
-        emit!(FundsReleased {
-            escrow: escrow.key(),
-            transaction_id: escrow.transaction_id.clone(),
-            amount: escrow.amount,
-            api: escrow.api,
-            timestamp: clock.unix_timestamp,
-        });
-
-        Ok(())
+        instructions::escrow::release_funds(ctx)
     }
 
     /// Resolve dispute with verifier oracle signature
     ///
-    /// x402 Verifier Oracle assesses quality and signs a refund percentage.
-    /// This instruction validates the signature and splits funds accordingly.
+    /// x402 Verifier Oracle assesses quality and signs the score together
+    /// with the refund percentage (see `verifier_message`). This instruction
+    /// validates the signature and splits funds accordingly.
     ///
     /// # Arguments
     /// * `quality_score` - Quality score from verifier (0-100)
@@ -301,131 +220,7 @@ pub mod x402_escrow {
         refund_percentage: u8,
         signature: [u8; 64],
     ) -> Result<()> {
-        let escrow = &mut ctx.accounts.escrow;
-
-        require!(
-            escrow.status == EscrowStatus::Active || escrow.status == EscrowStatus::Disputed,
-            EscrowError::InvalidStatus
-        );
-
-        require!(quality_score <= 100, EscrowError::InvalidQualityScore);
-        require!(refund_percentage <= 100, EscrowError::InvalidRefundPercentage);
-
-        // Verify signature from verifier oracle
-        // Message format: "{transaction_id}:{quality_score}"
-        let message = format!("{}:{}", escrow.transaction_id, quality_score);
-        let message_bytes = message.as_bytes();
-
-        // Verify Ed25519 signature from the instructions sysvar
-        verify_ed25519_signature(
-            &ctx.accounts.instructions_sysvar,
-            &signature,
-            ctx.accounts.verifier.key,
-            message_bytes,
-        )?;
-
-        msg!("Verifier: {}", ctx.accounts.verifier.key());
-        msg!("Quality Score: {}", quality_score);
-        msg!("Refund: {}%", refund_percentage);
-
-        // Calculate split amounts
-        let refund_amount = (escrow.amount as u128)
-            .checked_mul(refund_percentage as u128)
-            .ok_or(EscrowError::ArithmeticOverflow)?
-            .checked_div(100)
-            .ok_or(EscrowError::ArithmeticOverflow)? as u64;
-
-        let payment_amount = escrow.amount - refund_amount;
-
-        msg!("Refund to Agent: {} SOL", refund_amount as f64 / 1_000_000_000.0);
-        msg!("Payment to API: {} SOL", payment_amount as f64 / 1_000_000_000.0);
-
-        // Transfer refund to agent
-        // Note: Using direct lamport manipulation instead of system_program::transfer
-        // because escrow PDA contains data and system transfer requires empty accounts
-        if refund_amount > 0 {
-            **ctx.accounts.escrow.to_account_info().try_borrow_mut_lamports()? -= refund_amount;
-            **ctx.accounts.agent.to_account_info().try_borrow_mut_lamports()? += refund_amount;
-        }
-
-        // Transfer payment to API
-        if payment_amount > 0 {
-            **ctx.accounts.escrow.to_account_info().try_borrow_mut_lamports()? -= payment_amount;
-            **ctx.accounts.api.to_account_info().try_borrow_mut_lamports()? += payment_amount;
-        }
-
-        let escrow = &mut ctx.accounts.escrow;
-        escrow.status = EscrowStatus::Resolved;
-        escrow.quality_score = Some(quality_score);
-        escrow.refund_percentage = Some(refund_percentage);
-
-        // Update agent reputation
-        let agent_reputation = &mut ctx.accounts.agent_reputation;
-        let clock = Clock::get()?;
-
-        agent_reputation.total_transactions = agent_reputation.total_transactions.saturating_add(1);
-
-        // Update average quality received by agent
-        let total_quality = agent_reputation.average_quality_received as u64
-            * (agent_reputation.total_transactions.saturating_sub(1)) as u64
-            + quality_score as u64;
-        agent_reputation.average_quality_received =
-            (total_quality / agent_reputation.total_transactions as u64) as u8;
-
-        // Categorize dispute outcome for agent
-        if refund_percentage >= 75 {
-            agent_reputation.disputes_won = agent_reputation.disputes_won.saturating_add(1);
-        } else if refund_percentage >= 25 {
-            agent_reputation.disputes_partial = agent_reputation.disputes_partial.saturating_add(1);
-        } else {
-            agent_reputation.disputes_lost = agent_reputation.disputes_lost.saturating_add(1);
-        }
-
-        // Recalculate agent reputation score
-        agent_reputation.reputation_score = calculate_reputation_score(agent_reputation);
-        agent_reputation.last_updated = clock.unix_timestamp;
-
-        // Update API reputation (inverse of agent outcome)
-        let api_reputation = &mut ctx.accounts.api_reputation;
-        api_reputation.total_transactions = api_reputation.total_transactions.saturating_add(1);
-
-        // Quality delivered by API (inverse of refund percentage)
-        let quality_delivered = 100 - refund_percentage;
-        let total_quality_api = api_reputation.average_quality_received as u64
-            * (api_reputation.total_transactions.saturating_sub(1)) as u64
-            + quality_delivered as u64;
-        api_reputation.average_quality_received =
-            (total_quality_api / api_reputation.total_transactions as u64) as u8;
-
-        // Categorize for API (inverse)
-        if refund_percentage <= 25 {
-            // API provided good quality
-            api_reputation.disputes_won = api_reputation.disputes_won.saturating_add(1);
-        } else if refund_percentage <= 75 {
-            api_reputation.disputes_partial = api_reputation.disputes_partial.saturating_add(1);
-        } else {
-            // API provided poor quality
-            api_reputation.disputes_lost = api_reputation.disputes_lost.saturating_add(1);
-        }
-
-        api_reputation.reputation_score = calculate_reputation_score(api_reputation);
-        api_reputation.last_updated = clock.unix_timestamp;
-
-        msg!("Dispute resolved!");
-        msg!("Agent reputation: {}", agent_reputation.reputation_score);
-        msg!("API reputation: {}", api_reputation.reputation_score);
-
-        emit!(DisputeResolved {
-            escrow: escrow.key(),
-            transaction_id: escrow.transaction_id.clone(),
-            quality_score,
-            refund_percentage,
-            refund_amount,
-            payment_amount,
-            verifier: ctx.accounts.verifier.key(),
-        });
-
-        Ok(())
+        instructions::dispute::resolve_dispute(ctx, quality_score, refund_percentage, signature)
     }
 
     /// Resolve dispute with Switchboard On-Demand oracle
@@ -433,699 +228,567 @@ pub mod x402_escrow {
     /// Uses Switchboard decentralized oracle network for trustless quality assessment.
     /// The Switchboard Function calculates quality score off-chain and produces
     /// a cryptographically verified attestation that's validated on-chain.
+    /// The feed carries only the score and cannot commit to a work
+    /// agreement, so escrows with one must be resolved by `resolve_dispute`.
+    /// The refund is the complement of the attested score, so the caller
+    /// cannot choose the split.
     ///
     /// # Arguments
     /// * `quality_score` - Quality score from Switchboard Function (0-100)
-    /// * `refund_percentage` - Refund percentage from Switchboard (0-100)
     pub fn resolve_dispute_switchboard(
         ctx: Context<ResolveDisputeSwitchboard>,
         quality_score: u8,
-        refund_percentage: u8,
     ) -> Result<()> {
-        let escrow = &mut ctx.accounts.escrow;
-
-        require!(
-            escrow.status == EscrowStatus::Active || escrow.status == EscrowStatus::Disputed,
-            EscrowError::InvalidStatus
-        );
-
-        require!(quality_score <= 100, EscrowError::InvalidQualityScore);
-        require!(refund_percentage <= 100, EscrowError::InvalidRefundPercentage);
-
-        // Verify Switchboard attestation
-        // The Switchboard Function result is stored in pull_feed account
-        // and contains the quality score signed by oracle nodes
-        let pull_feed = &ctx.accounts.switchboard_function;
-
-        // Load and verify the Switchboard attestation
-        let feed_account_info = pull_feed.to_account_info();
-        let feed_data = PullFeedAccountData::parse(feed_account_info.data.borrow())
-            .map_err(|_| EscrowError::InvalidSwitchboardAttestation)?;
-
-        // Validate timestamp freshness (attestation must be within 300 seconds)
-        let clock = Clock::get()?;
-        let age_seconds = clock.unix_timestamp - feed_data.last_update_timestamp;
-
-        require!(
-            age_seconds >= 0 && age_seconds <= 300,
-            EscrowError::StaleAttestation
-        );
-
-        msg!("Switchboard attestation age: {} seconds", age_seconds);
-
-        // Extract quality score from Switchboard result
-        // The value is encoded as i128 in the feed
-        let switchboard_quality = feed_data.result.value;
-
-        // Verify the quality score matches what was submitted
-        require!(
-            switchboard_quality == quality_score as i128,
-            EscrowError::QualityScoreMismatch
-        );
-
-        msg!("Switchboard Quality Score: {}", quality_score);
-        msg!("Refund: {}%", refund_percentage);
-
-        // Calculate split amounts (same logic as resolve_dispute)
-        let refund_amount = (escrow.amount as u128)
-            .checked_mul(refund_percentage as u128)
-            .ok_or(EscrowError::ArithmeticOverflow)?
-            .checked_div(100)
-            .ok_or(EscrowError::ArithmeticOverflow)? as u64;
-
-        let payment_amount = escrow.amount - refund_amount;
-
-        msg!("Refund to Agent: {} SOL", refund_amount as f64 / 1_000_000_000.0);
-        msg!("Payment to API: {} SOL", payment_amount as f64 / 1_000_000_000.0);
-
-        // Transfer refund to agent
-        // Note: Using direct lamport manipulation instead of system_program::transfer
-        // because escrow PDA contains data and system transfer requires empty accounts
-        if refund_amount > 0 {
-            **ctx.accounts.escrow.to_account_info().try_borrow_mut_lamports()? -= refund_amount;
-            **ctx.accounts.agent.to_account_info().try_borrow_mut_lamports()? += refund_amount;
-        }
-
-        // Transfer payment to API
-        if payment_amount > 0 {
-            **ctx.accounts.escrow.to_account_info().try_borrow_mut_lamports()? -= payment_amount;
-            **ctx.accounts.api.to_account_info().try_borrow_mut_lamports()? += payment_amount;
-        }
-
-        let escrow = &mut ctx.accounts.escrow;
-        escrow.status = EscrowStatus::Resolved;
-        escrow.quality_score = Some(quality_score);
-        escrow.refund_percentage = Some(refund_percentage);
-
-        // Update agent reputation (same logic as resolve_dispute)
-        let agent_reputation = &mut ctx.accounts.agent_reputation;
-        let clock = Clock::get()?;
-
-        agent_reputation.total_transactions = agent_reputation.total_transactions.saturating_add(1);
-
-        let total_quality = agent_reputation.average_quality_received as u64
-            * (agent_reputation.total_transactions.saturating_sub(1)) as u64
-            + quality_score as u64;
-        agent_reputation.average_quality_received =
-            (total_quality / agent_reputation.total_transactions as u64) as u8;
-
-        if refund_percentage >= 75 {
-            agent_reputation.disputes_won = agent_reputation.disputes_won.saturating_add(1);
-        } else if refund_percentage >= 25 {
-            agent_reputation.disputes_partial = agent_reputation.disputes_partial.saturating_add(1);
-        } else {
-            agent_reputation.disputes_lost = agent_reputation.disputes_lost.saturating_add(1);
-        }
-
-        agent_reputation.reputation_score = calculate_reputation_score(agent_reputation);
-        agent_reputation.last_updated = clock.unix_timestamp;
-
-        // Update API reputation
-        let api_reputation = &mut ctx.accounts.api_reputation;
-        api_reputation.total_transactions = api_reputation.total_transactions.saturating_add(1);
-
-        let quality_delivered = 100 - refund_percentage;
-        let total_quality_api = api_reputation.average_quality_received as u64
-            * (api_reputation.total_transactions.saturating_sub(1)) as u64
-            + quality_delivered as u64;
-        api_reputation.average_quality_received =
-            (total_quality_api / api_reputation.total_transactions as u64) as u8;
-
-        if refund_percentage <= 25 {
-            api_reputation.disputes_won = api_reputation.disputes_won.saturating_add(1);
-        } else if refund_percentage <= 75 {
-            api_reputation.disputes_partial = api_reputation.disputes_partial.saturating_add(1);
-        } else {
-            api_reputation.disputes_lost = api_reputation.disputes_lost.saturating_add(1);
-        }
-
-        api_reputation.reputation_score = calculate_reputation_score(api_reputation);
-        api_reputation.last_updated = clock.unix_timestamp;
-
-        msg!("Dispute resolved via Switchboard!");
-        msg!("Agent reputation: {}", agent_reputation.reputation_score);
-        msg!("API reputation: {}", api_reputation.reputation_score);
-
-        emit!(DisputeResolved {
-            escrow: escrow.key(),
-            transaction_id: escrow.transaction_id.clone(),
-            quality_score,
-            refund_percentage,
-            refund_amount,
-            payment_amount,
-            verifier: ctx.accounts.switchboard_function.key(),
-        });
-
-        Ok(())
+        instructions::dispute::resolve_dispute_switchboard(ctx, quality_score)
+    }
+
+    /// Settle an escrow against its SLA from a signed measurement
+    ///
+    /// Permissionless and needs no prior dispute: an accepted verifier signs
+    /// the observed latency and availability, and the refund is read from
+    /// `SLA_REFUND_TABLE`. Missing the delivery deadline refunds in full.
+    ///
+    /// Message format: see `sla_message`.
+    ///
+    /// # Arguments
+    /// * `latency_ms` - Measured response latency
+    /// * `availability_bps` - Measured availability (basis points, 0-10000)
+    /// * `signature` - Ed25519 signature from the verifier
+    pub fn settle_sla(
+        ctx: Context<SettleSla>,
+        latency_ms: u32,
+        availability_bps: u16,
+        signature: [u8; 64],
+    ) -> Result<()> {
+        instructions::dispute::settle_sla(ctx, latency_ms, availability_bps, signature)
+    }
+
+    /// Apply the default split to a dispute no verifier resolved in time
+    ///
+    /// Permissionless once `resolution_deadline` has passed, so a disputed
+    /// escrow can never stay locked. Splits by
+    /// `ProgramConfig::default_refund_percentage`; the dispute bond follows
+    /// the same share.
+    pub fn settle_unresolved(ctx: Context<SettleUnresolved>) -> Result<()> {
+        instructions::dispute::settle_unresolved(ctx)
     }
 
     /// Mark escrow as disputed (agent initiates dispute)
     pub fn mark_disputed(ctx: Context<MarkDisputed>) -> Result<()> {
-        let escrow = &mut ctx.accounts.escrow;
-        let reputation = &mut ctx.accounts.reputation;
-
-        require!(
-            escrow.status == EscrowStatus::Active,
-            EscrowError::InvalidStatus
-        );
-
-        require!(
-            ctx.accounts.agent.key() == escrow.agent,
-            EscrowError::Unauthorized
-        );
-
-        // Check if dispute window is still open (before time lock expires)
-        let clock = Clock::get()?;
-        require!(
-            clock.unix_timestamp < escrow.expires_at,
-            EscrowError::DisputeWindowExpired
-        );
-
-        // Calculate dispute cost based on reputation
-        let dispute_cost = calculate_dispute_cost(reputation);
-        require!(
-            ctx.accounts.agent.lamports() >= dispute_cost,
-            EscrowError::InsufficientDisputeFunds
-        );
-
-        // Update reputation - record dispute filed
-        reputation.disputes_filed = reputation.disputes_filed.saturating_add(1);
-
-        escrow.status = EscrowStatus::Disputed;
-
-        msg!("Escrow marked as disputed (cost: {} lamports)", dispute_cost);
-
-        emit!(DisputeMarked {
-            escrow: escrow.key(),
-            agent: escrow.agent,
-            transaction_id: escrow.transaction_id.clone(),
-            timestamp: clock.unix_timestamp,
-        });
-
-        Ok(())
+        instructions::escrow::mark_disputed(ctx)
     }
 
     /// Initialize or update entity reputation
     pub fn init_reputation(ctx: Context<InitReputation>) -> Result<()> {
-        let reputation = &mut ctx.accounts.reputation;
-        let clock = Clock::get()?;
-
-        reputation.entity = ctx.accounts.entity.key();
-        reputation.entity_type = EntityType::Agent;
-        reputation.total_transactions = 0;
-        reputation.disputes_filed = 0;
-        reputation.disputes_won = 0;
-        reputation.disputes_partial = 0;
-        reputation.disputes_lost = 0;
-        reputation.average_quality_received = 0;
-        reputation.reputation_score = 500; // Start at medium
-        reputation.created_at = clock.unix_timestamp;
-        reputation.last_updated = clock.unix_timestamp;
-        reputation.bump = ctx.bumps.reputation;
-
-        msg!("Reputation initialized for {}", ctx.accounts.entity.key());
-
-        Ok(())
+        instructions::reputation::init_reputation(ctx)
     }
 
-    /// Update reputation after transaction completes
-    /// Only callable by the escrow program itself during resolve_dispute
-    pub fn update_reputation(
-        ctx: Context<UpdateReputation>,
-        quality_score: u8,
-        refund_percentage: u8,
+    /// Publish the minimum reputation score required of counterparties
+    ///
+    /// Providers use this to set a floor for agents, and agents a floor for
+    /// providers. Enforced by `initialize_escrow`.
+    ///
+    /// # Arguments
+    /// * `min_score` - Minimum counterparty reputation score (0-1000)
+    pub fn set_min_counterparty_reputation(
+        ctx: Context<SetMinCounterpartyReputation>,
+        min_score: u16,
     ) -> Result<()> {
-        // Authorization: Only allow updates from program-owned accounts
-        // In practice, this should be called via CPI from resolve_dispute
-        let reputation = &mut ctx.accounts.reputation;
-        let clock = Clock::get()?;
-
-        reputation.total_transactions = reputation.total_transactions.saturating_add(1);
-
-        // Update average quality received
-        let total_quality = reputation.average_quality_received as u64
-            * (reputation.total_transactions - 1) as u64
-            + quality_score as u64;
-        reputation.average_quality_received = (total_quality / reputation.total_transactions as u64) as u8;
-
-        // Categorize dispute outcome
-        if refund_percentage >= 75 {
-            reputation.disputes_won = reputation.disputes_won.saturating_add(1);
-        } else if refund_percentage >= 25 {
-            reputation.disputes_partial = reputation.disputes_partial.saturating_add(1);
-        } else {
-            reputation.disputes_lost = reputation.disputes_lost.saturating_add(1);
-        }
-
-        // Calculate new reputation score (0-1000)
-        reputation.reputation_score = calculate_reputation_score(reputation);
-        reputation.last_updated = clock.unix_timestamp;
-
-        msg!("Reputation updated: score = {}", reputation.reputation_score);
-
-        Ok(())
+        instructions::reputation::set_min_counterparty_reputation(ctx, min_score)
     }
 
-    /// Rate limit check - ensures entity hasn't exceeded limits
-    pub fn check_rate_limit(ctx: Context<CheckRateLimit>) -> Result<()> {
-        let rate_limiter = &mut ctx.accounts.rate_limiter;
-        let clock = Clock::get()?;
-        let current_hour = clock.unix_timestamp / 3600;
-        let current_day = clock.unix_timestamp / 86400;
-
-        // Reset hourly counter if hour changed
-        if current_hour > rate_limiter.last_hour_check {
-            rate_limiter.transactions_last_hour = 0;
-            rate_limiter.last_hour_check = current_hour;
-        }
-
-        // Reset daily counter if day changed
-        if current_day > rate_limiter.last_day_check {
-            rate_limiter.transactions_last_day = 0;
-            rate_limiter.disputes_last_day = 0;
-            rate_limiter.last_day_check = current_day;
-        }
-
-        // Get limits based on verification level
-        let (hour_limit, day_limit, _dispute_day_limit) = get_rate_limits(rate_limiter.verification_level.clone());
-
-        // Check limits
-        require!(
-            rate_limiter.transactions_last_hour < hour_limit,
-            EscrowError::RateLimitExceeded
-        );
-        require!(
-            rate_limiter.transactions_last_day < day_limit,
-            EscrowError::RateLimitExceeded
-        );
-
-        // Increment counters
-        rate_limiter.transactions_last_hour = rate_limiter.transactions_last_hour.saturating_add(1);
-        rate_limiter.transactions_last_day = rate_limiter.transactions_last_day.saturating_add(1);
-
-        Ok(())
+    /// Initialize the history account for an agent/API pair
+    ///
+    /// Permissionless; must exist before escrows between the pair settle.
+    pub fn init_relationship(ctx: Context<InitRelationship>) -> Result<()> {
+        instructions::reputation::init_relationship(ctx)
     }
-}
 
-// Helper functions
-fn calculate_dispute_cost(reputation: &EntityReputation) -> u64 {
-    if reputation.total_transactions == 0 {
-        return BASE_DISPUTE_COST;
+    /// Publish per-agent limits for escrows opened to this provider
+    ///
+    /// Applied by `initialize_escrow` to every agent individually, on top
+    /// of the global `VerificationLevel` limits. A zero disables that limit.
+    ///
+    /// # Arguments
+    /// * `max_escrows_per_hour` - Escrows one agent may open per hour
+    /// * `max_open_escrows` - Unsettled escrows one agent may hold at once
+    /// * `max_locked_value` - Lamports one agent may hold in unsettled escrows
+    pub fn init_provider_limits(
+        ctx: Context<InitProviderLimits>,
+        max_escrows_per_hour: u16,
+        max_open_escrows: u32,
+        max_locked_value: u64,
+    ) -> Result<()> {
+        instructions::provider::init_provider_limits(
+            ctx,
+            max_escrows_per_hour,
+            max_open_escrows,
+            max_locked_value,
+        )
     }
 
-    let dispute_rate = (reputation.disputes_filed * 100) / reputation.total_transactions;
-
-    let multiplier = match dispute_rate {
-        0..=20 => 1,     // Normal dispute rate
-        21..=40 => 2,    // High dispute rate
-        41..=60 => 5,    // Very high dispute rate
-        _ => 10,         // Abuse pattern
-    };
-
-    BASE_DISPUTE_COST.saturating_mul(multiplier)
-}
-
-fn calculate_reputation_score(reputation: &EntityReputation) -> u16 {
-    if reputation.total_transactions == 0 {
-        return 500; // Default medium score
+    /// Change the provider's per-agent limits (see `init_provider_limits`)
+    pub fn set_provider_limits(
+        ctx: Context<SetProviderLimits>,
+        max_escrows_per_hour: u16,
+        max_open_escrows: u32,
+        max_locked_value: u64,
+    ) -> Result<()> {
+        instructions::provider::set_provider_limits(
+            ctx,
+            max_escrows_per_hour,
+            max_open_escrows,
+            max_locked_value,
+        )
     }
 
-    let tx_score = reputation.total_transactions.min(100) as u16 * 5; // Max 500 from transactions
-
-    let dispute_score = if reputation.disputes_filed > 0 {
-        let win_rate = (reputation.disputes_won * 100) / reputation.disputes_filed;
-        (win_rate as u16 * 3).min(300) // Max 300 from dispute wins
-    } else {
-        150 // No disputes, neutral
-    };
-
-    let quality_score = (reputation.average_quality_received as u16 * 2).min(200); // Max 200 from quality
-
-    (tx_score + dispute_score + quality_score).min(1000)
-}
-
-fn get_rate_limits(verification: VerificationLevel) -> (u16, u16, u16) {
-    match verification {
-        VerificationLevel::Basic => (1, 10, 3),        // 1/hour, 10/day, 3 disputes/day
-        VerificationLevel::Staked => (10, 100, 10),    // 10/hour, 100/day, 10 disputes/day
-        VerificationLevel::Social => (50, 500, 50),    // 50/hour, 500/day, 50 disputes/day
-        VerificationLevel::KYC => (1000, 10000, 1000), // Unlimited
+    /// Publish the provider's terms (API signs and pays)
+    ///
+    /// Once registered, `initialize_escrow` routes payment to `payout` and
+    /// rejects escrows below `default_price` or from agents under
+    /// `min_agent_reputation`; resolution only accepts listed verifiers.
+    ///
+    /// # Arguments
+    /// * `payout` - Address that receives payments and forfeited bonds
+    /// * `endpoint_hash` - Hash of the endpoint or service identifier
+    /// * `default_time_lock` - Time lock for escrows opened with `time_lock = 0` (seconds)
+    /// * `default_price` - Minimum escrow amount (lamports, 0 = any)
+    /// * `accepted_verifiers` - Verifier keys or Switchboard feeds (empty = config defaults)
+    /// * `min_agent_reputation` - Reputation score required of agents (0-1000)
+    pub fn register_provider(
+        ctx: Context<RegisterProvider>,
+        payout: Pubkey,
+        endpoint_hash: [u8; 32],
+        default_time_lock: i64,
+        default_price: u64,
+        accepted_verifiers: Vec<Pubkey>,
+        min_agent_reputation: u16,
+    ) -> Result<()> {
+        instructions::provider::register_provider(
+            ctx,
+            payout,
+            endpoint_hash,
+            default_time_lock,
+            default_price,
+            accepted_verifiers,
+            min_agent_reputation,
+        )
     }
-}
 
-// ============================================================================
-// Account Structs
-// ============================================================================
-
-#[derive(Accounts)]
-#[instruction(amount: u64, time_lock: i64, transaction_id: String)]
-pub struct InitializeEscrow<'info> {
-    #[account(
-        init,
-        payer = agent,
-        space = 8 + Escrow::INIT_SPACE,
-        seeds = [b"escrow", transaction_id.as_bytes()],
-        bump
-    )]
-    pub escrow: Account<'info, Escrow>,
-
-    #[account(mut)]
-    pub agent: Signer<'info>,
-
-    /// CHECK: API wallet address
-    pub api: AccountInfo<'info>,
-
-    pub system_program: Program<'info, System>,
-}
-
-#[derive(Accounts)]
-pub struct ReleaseFunds<'info> {
-    #[account(
-        mut,
-        seeds = [b"escrow", escrow.transaction_id.as_bytes()],
-        bump = escrow.bump
-    )]
-    pub escrow: Account<'info, Escrow>,
-
-    #[account(mut)]
-    pub agent: Signer<'info>,
+    /// Change the provider's published terms (see `register_provider`)
+    pub fn update_provider(
+        ctx: Context<UpdateProvider>,
+        payout: Pubkey,
+        endpoint_hash: [u8; 32],
+        default_time_lock: i64,
+        default_price: u64,
+        accepted_verifiers: Vec<Pubkey>,
+        min_agent_reputation: u16,
+    ) -> Result<()> {
+        instructions::provider::update_provider(
+            ctx,
+            payout,
+            endpoint_hash,
+            default_time_lock,
+            default_price,
+            accepted_verifiers,
+            min_agent_reputation,
+        )
+    }
 
-    /// CHECK: API wallet address
-    #[account(mut)]
-    pub api: AccountInfo<'info>,
+    /// Set how long agents have to dispute the provider's escrows
+    ///
+    /// Snapshotted onto each new escrow. The window runs from confirmed
+    /// delivery (or creation, if delivery is never confirmed), independent
+    /// of the time lock, so "dispute within 2h, auto-release at 24h" works.
+    ///
+    /// # Arguments
+    /// * `dispute_window` - Window length in seconds, or 0 for the default
+    pub fn set_provider_dispute_window(ctx: Context<UpdateProvider>, dispute_window: i64) -> Result<()> {
+        instructions::provider::set_provider_dispute_window(ctx, dispute_window)
+    }
 
-    pub system_program: Program<'info, System>,
-}
+    /// Open a collateral bond for the provider (API signs and pays rent)
+    ///
+    /// Once a bond exists, every new escrow reserves its own amount from it,
+    /// so a verifier can award refunds of up to 200% of the payment.
+    pub fn init_provider_bond(ctx: Context<InitProviderBond>) -> Result<()> {
+        instructions::provider::init_provider_bond(ctx)
+    }
 
-#[derive(Accounts)]
-pub struct ResolveDispute<'info> {
-    #[account(
-        mut,
-        seeds = [b"escrow", escrow.transaction_id.as_bytes()],
-        bump = escrow.bump
-    )]
-    pub escrow: Account<'info, Escrow>,
-
-    #[account(mut)]
-    pub agent: SystemAccount<'info>,
-
-    /// CHECK: API wallet address
-    #[account(mut)]
-    pub api: AccountInfo<'info>,
-
-    /// CHECK: Verifier oracle public key
-    pub verifier: AccountInfo<'info>,
-
-    /// CHECK: Instructions sysvar for Ed25519 signature verification
-    #[account(address = INSTRUCTIONS_ID)]
-    pub instructions_sysvar: AccountInfo<'info>,
-
-    #[account(
-        mut,
-        seeds = [b"reputation", agent.key().as_ref()],
-        bump = agent_reputation.bump
-    )]
-    pub agent_reputation: Account<'info, EntityReputation>,
-
-    #[account(
-        mut,
-        seeds = [b"reputation", api.key().as_ref()],
-        bump = api_reputation.bump
-    )]
-    pub api_reputation: Account<'info, EntityReputation>,
-
-    pub system_program: Program<'info, System>,
-}
+    /// Add collateral to the provider's bond
+    pub fn post_bond(ctx: Context<UpdateProviderBond>, amount: u64) -> Result<()> {
+        instructions::provider::post_bond(ctx, amount)
+    }
 
-#[derive(Accounts)]
-pub struct ResolveDisputeSwitchboard<'info> {
-    #[account(
-        mut,
-        seeds = [b"escrow", escrow.transaction_id.as_bytes()],
-        bump = escrow.bump
-    )]
-    pub escrow: Account<'info, Escrow>,
-
-    #[account(mut)]
-    pub agent: SystemAccount<'info>,
-
-    /// CHECK: API wallet address
-    #[account(mut)]
-    pub api: AccountInfo<'info>,
-
-    /// Switchboard Function pull feed containing quality score
-    /// CHECK: Validated via PullFeedAccountData::parse
-    pub switchboard_function: AccountInfo<'info>,
-
-    #[account(
-        mut,
-        seeds = [b"reputation", agent.key().as_ref()],
-        bump = agent_reputation.bump
-    )]
-    pub agent_reputation: Account<'info, EntityReputation>,
-
-    #[account(
-        mut,
-        seeds = [b"reputation", api.key().as_ref()],
-        bump = api_reputation.bump
-    )]
-    pub api_reputation: Account<'info, EntityReputation>,
-
-    pub system_program: Program<'info, System>,
-}
+    /// Withdraw collateral not reserved by open escrows
+    pub fn withdraw_bond(ctx: Context<UpdateProviderBond>, amount: u64) -> Result<()> {
+        instructions::provider::withdraw_bond(ctx, amount)
+    }
 
-#[derive(Accounts)]
-pub struct MarkDisputed<'info> {
-    #[account(
-        mut,
-        seeds = [b"escrow", escrow.transaction_id.as_bytes()],
-        bump = escrow.bump
-    )]
-    pub escrow: Account<'info, Escrow>,
-
-    #[account(
-        mut,
-        seeds = [b"reputation", agent.key().as_ref()],
-        bump = reputation.bump
-    )]
-    pub reputation: Account<'info, EntityReputation>,
-
-    #[account(mut)]
-    pub agent: Signer<'info>,
-}
+    /// Initialize the strike and suspension record for a provider
+    ///
+    /// Optional: dispute resolution creates it on first use. Permissionless
+    /// so either party can create it ahead of time.
+    pub fn init_provider_penalties(ctx: Context<InitProviderPenalties>) -> Result<()> {
+        instructions::provider::init_provider_penalties(ctx)
+    }
 
-#[derive(Accounts)]
-pub struct InitReputation<'info> {
-    #[account(
-        init,
-        payer = payer,
-        space = 8 + EntityReputation::INIT_SPACE,
-        seeds = [b"reputation", entity.key().as_ref()],
-        bump
-    )]
-    pub reputation: Account<'info, EntityReputation>,
+    /// Appeal the provider's current suspension
+    ///
+    /// Locks `ProgramConfig::appeal_bond` in the appeal account until
+    /// governance decides. One appeal may be open per provider.
+    ///
+    /// # Arguments
+    /// * `evidence_hash` - Hash of the off-chain evidence bundle
+    pub fn file_appeal(ctx: Context<FileAppeal>, evidence_hash: [u8; 32]) -> Result<()> {
+        instructions::provider::file_appeal(ctx, evidence_hash)
+    }
 
-    /// CHECK: Entity being tracked
-    pub entity: AccountInfo<'info>,
+    /// Decide a provider's appeal (config authority only)
+    ///
+    /// Approval lifts the suspension, clears strikes and returns the bond.
+    /// Rejection sends the bond to the treasury. Either way the appeal
+    /// account is closed and its rent returned to the provider.
+    ///
+    /// # Arguments
+    /// * `approved` - Whether the provider is reinstated
+    pub fn resolve_appeal(ctx: Context<ResolveAppeal>, approved: bool) -> Result<()> {
+        instructions::provider::resolve_appeal(ctx, approved)
+    }
 
-    #[account(mut)]
-    pub payer: Signer<'info>,
+    /// Record a dispute outcome against a reputation by hand
+    ///
+    /// Dispute resolution updates both parties itself; this is the config
+    /// authority's correction path for outcomes settled elsewhere.
+    pub fn update_reputation(
+        ctx: Context<UpdateReputation>,
+        quality_score: u8,
+        refund_percentage: u8,
+    ) -> Result<()> {
+        instructions::reputation::update_reputation(ctx, quality_score, refund_percentage)
+    }
 
-    pub system_program: Program<'info, System>,
-}
+    /// Rate limit check - ensures entity hasn't exceeded limits
+    pub fn check_rate_limit(ctx: Context<CheckRateLimit>) -> Result<()> {
+        instructions::rate_limit::check_rate_limit(ctx)
+    }
 
-#[derive(Accounts)]
-pub struct UpdateReputation<'info> {
-    #[account(
-        mut,
-        seeds = [b"reputation", reputation.entity.as_ref()],
-        bump = reputation.bump
-    )]
-    pub reputation: Account<'info, EntityReputation>,
-
-    /// Authority that can update reputation (restricted)
-    pub authority: Signer<'info>,
-}
+    /// Initialize the rate limiter for an entity at `Basic` verification
+    pub fn init_rate_limiter(ctx: Context<InitRateLimiter>) -> Result<()> {
+        instructions::rate_limit::init_rate_limiter(ctx)
+    }
 
-#[derive(Accounts)]
-pub struct CheckRateLimit<'info> {
-    #[account(
-        mut,
-        seeds = [b"rate_limit", entity.key().as_ref()],
-        bump = rate_limiter.bump
-    )]
-    pub rate_limiter: Account<'info, RateLimiter>,
+    /// Initialize the stake vault for an entity
+    pub fn init_stake_vault(ctx: Context<InitStakeVault>) -> Result<()> {
+        instructions::stake::init_stake_vault(ctx)
+    }
 
-    pub entity: Signer<'info>,
-}
+    /// Lock SOL in the entity's stake vault
+    ///
+    /// Raises the entity to `VerificationLevel::Staked` once the active stake
+    /// reaches the configured `staked_level_threshold`.
+    ///
+    /// # Arguments
+    /// * `amount` - Lamports to stake
+    pub fn stake(ctx: Context<Stake>, amount: u64) -> Result<()> {
+        instructions::stake::stake(ctx, amount)
+    }
 
-// ============================================================================
-// State
-// ============================================================================
-
-#[account]
-#[derive(InitSpace)]
-pub struct Escrow {
-    pub agent: Pubkey,                    // 32
-    pub api: Pubkey,                      // 32
-    pub amount: u64,                      // 8
-    pub status: EscrowStatus,             // 1 + 1
-    pub created_at: i64,                  // 8
-    pub expires_at: i64,                  // 8
-    #[max_len(64)]
-    pub transaction_id: String,           // 4 + 64
-    pub bump: u8,                         // 1
-    pub quality_score: Option<u8>,        // 1 + 1
-    pub refund_percentage: Option<u8>,    // 1 + 1
-}
+    /// Begin unbonding part of the active stake
+    ///
+    /// Unbonding stake stops counting towards the verification level
+    /// immediately and can be withdrawn after `UNSTAKE_UNBONDING_PERIOD`.
+    /// A new request restarts the unbonding timer for the whole pending amount.
+    ///
+    /// # Arguments
+    /// * `amount` - Lamports to unbond
+    pub fn request_unstake(ctx: Context<RequestUnstake>, amount: u64) -> Result<()> {
+        instructions::stake::request_unstake(ctx, amount)
+    }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
-pub enum EscrowStatus {
-    Active,      // Payment locked, awaiting resolution
-    Released,    // Funds released to API (happy path)
-    Disputed,    // Agent disputed quality
-    Resolved,    // Dispute resolved with refund split
-}
+    /// Withdraw stake whose unbonding period has elapsed
+    pub fn withdraw_unstaked(ctx: Context<WithdrawUnstaked>) -> Result<()> {
+        instructions::stake::withdraw_unstaked(ctx)
+    }
 
-/// Entity Reputation - tracks agent/provider performance on-chain
-#[account]
-#[derive(InitSpace)]
-pub struct EntityReputation {
-    pub entity: Pubkey,                   // 32
-    pub entity_type: EntityType,          // 1 + 1
-    pub total_transactions: u64,          // 8
-    pub disputes_filed: u64,              // 8
-    pub disputes_won: u64,                // 8 - Quality <50
-    pub disputes_partial: u64,            // 8 - Quality 50-79
-    pub disputes_lost: u64,               // 8 - Quality >=80
-    pub average_quality_received: u8,     // 1
-    pub reputation_score: u16,            // 2 - 0-1000 score
-    pub created_at: i64,                  // 8
-    pub last_updated: i64,                // 8
-    pub bump: u8,                         // 1
-}
+    /// Open a vault that funds the agent's session keys and any escrow it
+    /// opens with `fund_from_vault`
+    pub fn init_agent_vault(ctx: Context<InitAgentVault>) -> Result<()> {
+        instructions::vault::init_agent_vault(ctx)
+    }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
-pub enum EntityType {
-    Agent,
-    Provider,
-}
+    /// Move lamports from the owner's wallet into its vault
+    pub fn deposit_to_vault(ctx: Context<UpdateAgentVault>, amount: u64) -> Result<()> {
+        instructions::vault::deposit_to_vault(ctx, amount)
+    }
 
-/// Rate Limiter - prevents spam and abuse
-#[account]
-#[derive(InitSpace)]
-pub struct RateLimiter {
-    pub entity: Pubkey,                   // 32
-    pub verification_level: VerificationLevel, // 1 + 1
-    pub transactions_last_hour: u16,      // 2
-    pub transactions_last_day: u16,       // 2
-    pub disputes_last_day: u16,           // 2
-    pub last_hour_check: i64,             // 8
-    pub last_day_check: i64,              // 8
-    pub bump: u8,                         // 1
-}
+    /// Move lamports above the rent reserve back to the owner
+    pub fn withdraw_from_vault(ctx: Context<UpdateAgentVault>, amount: u64) -> Result<()> {
+        instructions::vault::withdraw_from_vault(ctx, amount)
+    }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
-pub enum VerificationLevel {
-    Basic,       // Just wallet (low limits)
-    Staked,      // 1+ SOL staked (medium limits)
-    Social,      // Twitter/GitHub linked (high limits)
-    KYC,         // Identity verified (unlimited)
-}
+    /// Authorize a hot key to open and dispute escrows for the owner
+    ///
+    /// The session key signs `initialize_escrow` and `mark_disputed` in
+    /// place of the owner; escrows and dispute bonds are paid from the
+    /// owner's vault and count against `spend_cap`.
+    ///
+    /// # Arguments
+    /// * `session_key` - Key held by the autonomous agent
+    /// * `expires_at` - Unix timestamp after which the key is rejected
+    /// * `max_per_escrow` - Largest escrow the key may open (lamports)
+    /// * `spend_cap` - Total the key may spend on escrows and bonds (lamports)
+    /// * `allowed_providers` - Providers the key may pay (empty = any)
+    pub fn create_session_key(
+        ctx: Context<CreateSessionKey>,
+        session_key: Pubkey,
+        expires_at: i64,
+        max_per_escrow: u64,
+        spend_cap: u64,
+        allowed_providers: Vec<Pubkey>,
+    ) -> Result<()> {
+        instructions::vault::create_session_key(
+            ctx,
+            session_key,
+            expires_at,
+            max_per_escrow,
+            spend_cap,
+            allowed_providers,
+        )
+    }
 
-/// Work Agreement - structured scope definition
-#[account]
-#[derive(InitSpace)]
-pub struct WorkAgreement {
-    pub escrow: Pubkey,                   // 32
-    #[max_len(128)]
-    pub query: String,                    // 4 + 128
-    pub required_fields: u8,              // 1 - bitmask or count
-    pub min_records: u32,                 // 4
-    pub max_age_days: u32,                // 4
-    pub min_quality_score: u8,            // 1
-    pub created_at: i64,                  // 8
-    pub bump: u8,                         // 1
-}
+    /// Revoke a session key and reclaim its rent
+    pub fn revoke_session_key(ctx: Context<RevokeSessionKey>) -> Result<()> {
+        instructions::vault::revoke_session_key(ctx)
+    }
 
-/// Provider Penalties - track strikes and suspensions
-#[account]
-#[derive(InitSpace)]
-pub struct ProviderPenalties {
-    pub provider: Pubkey,                 // 32
-    pub strike_count: u8,                 // 1
-    pub suspended: bool,                  // 1
-    pub suspension_end: Option<i64>,      // 1 + 8
-    pub total_refunds_issued: u64,        // 8
-    pub poor_quality_count: u32,          // 4 - Quality <30
-    pub created_at: i64,                  // 8
-    pub last_updated: i64,                // 8
-    pub bump: u8,                         // 1
-}
+    /// Create the program config, owned by the program's upgrade authority
+    pub fn init_config(ctx: Context<InitConfig>) -> Result<()> {
+        instructions::config::init_config(ctx)
+    }
 
-// ============================================================================
-// Errors
-// ============================================================================
+    /// Hand config authority to a new key (e.g. a governance program)
+    pub fn set_config_authority(ctx: Context<UpdateConfig>, new_authority: Pubkey) -> Result<()> {
+        instructions::config::set_config_authority(ctx, new_authority)
+    }
 
-#[error_code]
-pub enum EscrowError {
-    #[msg("Invalid escrow status for this operation")]
-    InvalidStatus,
+    /// Set the base dispute cost and the dispute-rate tier table
+    ///
+    /// # Arguments
+    /// * `base_dispute_cost` - Cost of a dispute at 1x (lamports)
+    /// * `tiers` - Tiers by ascending `max_rate` (disputes per 100
+    ///   transactions); the last must reach 100 to cover every rate
+    pub fn set_dispute_pricing(
+        ctx: Context<UpdateConfig>,
+        base_dispute_cost: u64,
+        tiers: [DisputeCostTier; DISPUTE_COST_TIERS],
+    ) -> Result<()> {
+        instructions::config::set_dispute_pricing(ctx, base_dispute_cost, tiers)
+    }
 
-    #[msg("Unauthorized: Only agent or expired escrow can release")]
-    Unauthorized,
+    /// Set how poor-quality outcomes turn into strikes and suspensions
+    ///
+    /// # Arguments
+    /// * `poor_quality_per_strike` - Poor-quality (<30) outcomes per strike
+    /// * `strikes_to_suspend` - Strikes that trigger a suspension
+    /// * `suspension_duration` - Length of a suspension (seconds)
+    pub fn set_penalty_policy(
+        ctx: Context<UpdateConfig>,
+        poor_quality_per_strike: u32,
+        strikes_to_suspend: u8,
+        suspension_duration: i64,
+    ) -> Result<()> {
+        instructions::config::set_penalty_policy(
+            ctx,
+            poor_quality_per_strike,
+            strikes_to_suspend,
+            suspension_duration,
+        )
+    }
 
-    #[msg("Invalid quality score (must be 0-100)")]
-    InvalidQualityScore,
+    /// Set the appeal bond, strike decay and the treasury for forfeited bonds
+    ///
+    /// # Arguments
+    /// * `appeal_bond` - Lamports locked by `file_appeal`
+    /// * `strike_decay_period` - Seconds without a new strike per strike removed
+    /// * `treasury` - Receives bonds from rejected appeals
+    pub fn set_appeal_policy(
+        ctx: Context<UpdateConfig>,
+        appeal_bond: u64,
+        strike_decay_period: i64,
+        treasury: Pubkey,
+    ) -> Result<()> {
+        instructions::config::set_appeal_policy(ctx, appeal_bond, strike_decay_period, treasury)
+    }
 
-    #[msg("Invalid refund percentage (must be 0-100)")]
-    InvalidRefundPercentage,
+    /// Set the resolution deadline and the split used by `settle_unresolved`
+    ///
+    /// # Arguments
+    /// * `resolution_period` - Seconds a verifier has after `mark_disputed`
+    /// * `default_refund_percentage` - Refund applied when none resolves (0-100)
+    pub fn set_unresolved_policy(
+        ctx: Context<UpdateConfig>,
+        resolution_period: i64,
+        default_refund_percentage: u8,
+    ) -> Result<()> {
+        instructions::config::set_unresolved_policy(
+            ctx,
+            resolution_period,
+            default_refund_percentage,
+        )
+    }
 
-    #[msg("Invalid verifier signature")]
-    InvalidSignature,
+    /// Set the active stake needed for `VerificationLevel::Staked`
+    ///
+    /// Existing stakers move to the new threshold on their next stake or
+    /// unstake.
+    ///
+    /// # Arguments
+    /// * `staked_level_threshold` - Lamports of active stake
+    pub fn set_staked_level_threshold(
+        ctx: Context<UpdateConfig>,
+        staked_level_threshold: u64,
+    ) -> Result<()> {
+        instructions::config::set_staked_level_threshold(ctx, staked_level_threshold)
+    }
 
-    #[msg("Invalid time lock: must be between 1 hour and 30 days")]
-    InvalidTimeLock,
+    /// Set the verifiers that judge escrows whose provider lists none
+    ///
+    /// Checked when the dispute is resolved, so a change applies to escrows
+    /// already open. An empty list leaves those escrows with no accepted
+    /// verifier until one is set.
+    ///
+    /// # Arguments
+    /// * `verifiers` - Verifier keys or Switchboard feeds
+    pub fn set_default_verifiers(
+        ctx: Context<UpdateConfig>,
+        verifiers: Vec<Pubkey>,
+    ) -> Result<()> {
+        instructions::config::set_default_verifiers(ctx, verifiers)
+    }
 
-    #[msg("Invalid amount: must be greater than 0")]
-    InvalidAmount,
+    /// Approve a key to attest `Social` or `KYC` verification
+    ///
+    /// # Arguments
+    /// * `max_level` - Highest level this attestor may grant
+    pub fn add_attestor(ctx: Context<AddAttestor>, max_level: VerificationLevel) -> Result<()> {
+        instructions::rate_limit::add_attestor(ctx, max_level)
+    }
 
-    #[msg("Invalid transaction ID: must be non-empty and max 64 chars")]
-    InvalidTransactionId,
+    /// Remove an attestor from the registry
+    ///
+    /// Attestations it already signed stay valid until they expire or the
+    /// attestor revokes them.
+    pub fn remove_attestor(ctx: Context<RemoveAttestor>) -> Result<()> {
+        instructions::rate_limit::remove_attestor(ctx)
+    }
 
-    #[msg("Time lock not expired: cannot release funds yet")]
-    TimeLockNotExpired,
+    /// Raise an entity's verification level with an attestor's signature
+    ///
+    /// Permissionless: anyone can relay a signed attestation. The level
+    /// lapses back automatically at `expires_at`.
+    ///
+    /// Message format: "attest:{entity}:{level}:{expires_at}:{nonce}" where
+    /// `level` is the `VerificationLevel` index (2 = Social, 3 = KYC) and
+    /// `nonce` is the limiter's current `attestation_nonce`. The nonce moves
+    /// on every attestation and revocation, so a signature can be relayed
+    /// only once and never after a revoke.
+    ///
+    /// # Arguments
+    /// * `level` - Level being attested (`Social` or `KYC`)
+    /// * `expires_at` - Unix timestamp after which the attestation lapses
+    /// * `signature` - Ed25519 signature from the attestor
+    pub fn attest_verification(
+        ctx: Context<AttestVerification>,
+        level: VerificationLevel,
+        expires_at: i64,
+        signature: [u8; 64],
+    ) -> Result<()> {
+        instructions::rate_limit::attest_verification(ctx, level, expires_at, signature)
+    }
 
-    #[msg("Dispute window expired: cannot dispute after time lock")]
-    DisputeWindowExpired,
+    /// Withdraw an attestation (attestor only)
+    pub fn revoke_verification(ctx: Context<RevokeVerification>) -> Result<()> {
+        instructions::rate_limit::revoke_verification(ctx)
+    }
 
-    #[msg("Amount too large: exceeds maximum escrow amount")]
-    AmountTooLarge,
+    /// Read-only view of an entity's reputation for CPI consumers
+    ///
+    /// Returns a [`ReputationSummary`] via return data. Does not mutate state.
+    pub fn get_reputation(ctx: Context<GetReputation>) -> Result<ReputationSummary> {
+        views::get_reputation(ctx)
+    }
 
-    #[msg("Insufficient funds to pay dispute cost")]
-    InsufficientDisputeFunds,
+    /// Read-only view of an escrow's state for CPI consumers
+    ///
+    /// Returns an [`EscrowSummary`] via return data. Does not mutate state.
+    pub fn get_escrow_status(ctx: Context<GetEscrowStatus>) -> Result<EscrowSummary> {
+        views::get_escrow_status(ctx)
+    }
 
-    #[msg("Rate limit exceeded: too many transactions")]
-    RateLimitExceeded,
+    /// Quote the cost an agent would pay to dispute right now
+    ///
+    /// Returns a [`DisputeQuote`] via return data. Does not mutate state.
+    /// Pass the config PDA as a trailing account to quote its pricing;
+    /// without it the default schedule is quoted.
+    pub fn quote_dispute_cost(ctx: Context<QuoteDisputeCost>) -> Result<DisputeQuote> {
+        views::quote_dispute_cost(ctx)
+    }
 
-    #[msg("Provider is suspended")]
-    ProviderSuspended,
+    /// Read-only view of an agent/API pair's history for CPI consumers
+    ///
+    /// Returns a [`RelationshipSummary`] via return data so providers can
+    /// price repeat customers. Does not mutate state.
+    pub fn get_relationship(ctx: Context<GetRelationship>) -> Result<RelationshipSummary> {
+        views::get_relationship(ctx)
+    }
 
-    #[msg("Reputation score too low for this operation")]
-    ReputationTooLow,
+    /// Read-only view of an entity's quality score distribution
+    ///
+    /// Returns a [`QualityDistribution`] via return data: the decile
+    /// histogram plus the exact mean and derived percentiles, so callers can
+    /// tell a consistently mediocre provider from a bimodal one.
+    pub fn get_quality_distribution(ctx: Context<GetReputation>) -> Result<QualityDistribution> {
+        views::get_quality_distribution(ctx)
+    }
 
-    #[msg("Arithmetic overflow in calculation")]
-    ArithmeticOverflow,
+    /// Read-only view of the terms agreed for an escrow
+    ///
+    /// Returns a [`WorkAgreementSummary`] via return data, including the
+    /// hash verifiers must sign over. Does not mutate state.
+    pub fn get_work_agreement(ctx: Context<GetWorkAgreement>) -> Result<WorkAgreementSummary> {
+        views::get_work_agreement(ctx)
+    }
 
-    #[msg("Insufficient rent reserve in escrow account")]
-    InsufficientRentReserve,
+    /// Upgrade an `Escrow` account written by an older program version
+    ///
+    /// Permissionless; the payer covers rent for any added space.
+    pub fn migrate_escrow(ctx: Context<MigrateAccount>) -> Result<()> {
+        instructions::migrate::migrate_escrow(ctx)
+    }
 
-    #[msg("Invalid Switchboard attestation")]
-    InvalidSwitchboardAttestation,
+    /// Upgrade an `EntityReputation` account written by an older program version
+    ///
+    /// Permissionless; the payer covers rent for any added space.
+    pub fn migrate_reputation(ctx: Context<MigrateAccount>) -> Result<()> {
+        instructions::migrate::migrate_reputation(ctx)
+    }
 
-    #[msg("Switchboard attestation is stale (older than 60 seconds)")]
-    StaleAttestation,
+    /// Upgrade a `ProviderPenalties` account written by an older program version
+    ///
+    /// Permissionless; the payer covers rent for any added space.
+    pub fn migrate_provider_penalties(ctx: Context<MigrateAccount>) -> Result<()> {
+        instructions::migrate::migrate_provider_penalties(ctx)
+    }
 
-    #[msg("Quality score mismatch between Switchboard and submitted value")]
-    QualityScoreMismatch,
+    /// Upgrade a `RateLimiter` account written by an older program version
+    ///
+    /// Permissionless; the payer covers rent for any added space.
+    pub fn migrate_rate_limiter(ctx: Context<MigrateAccount>) -> Result<()> {
+        instructions::migrate::migrate_rate_limiter(ctx)
+    }
 }
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,654 @@
+//! Account layouts and their versioning
+
+use anchor_lang::prelude::*;
+
+use crate::*;
+
+#[account]
+#[derive(InitSpace)]
+pub struct Escrow {
+    pub agent: Pubkey,                    // 32
+    pub api: Pubkey,                      // 32
+    pub amount: u64,                      // 8
+    pub status: EscrowStatus,             // 1 + 1
+    pub created_at: i64,                  // 8
+    pub expires_at: i64,                  // 8
+    #[max_len(64)]
+    pub transaction_id: String,           // 4 + 64
+    pub bump: u8,                         // 1
+    pub quality_score: Option<u8>,        // 1 + 1
+    pub refund_percentage: Option<u8>,    // 1 + 1
+    pub version: u8,                      // 1 - Layout version (0 = pre-versioning)
+    pub dispute_bond: u64,                // 8 - Dispute cost held until resolution
+    pub agreement_hash: Option<[u8; 32]>, // 1 + 32 - Set by create_work_agreement
+    pub response_hash: Option<[u8; 32]>,  // 1 + 32 - Set by confirm_delivery
+    pub record_count: u32,                // 4 - Records delivered
+    pub delivered_at: i64,                // 8 - 0 until delivery is confirmed
+    pub payout: Pubkey,                   // 32 - Receives the API's share
+    pub sla_max_latency_ms: u32,          // 4 - 0 = no latency SLA
+    pub sla_deadline: i64,                // 8 - Delivery deadline, 0 = none
+    pub bond_reserved: u64,               // 8 - Provider bond backing penalty refunds
+    pub dispute_window: i64,              // 8 - Provider's window at creation, 0 = default
+    pub resolution_deadline: i64,         // 8 - settle_unresolved opens here once disputed
+    pub metadata: Option<EscrowMetadata>, // 1 + 115 - Immutable after creation
+    pub funded_from_vault: bool,          // 1 - Refunds return to the agent's vault
+    pub accepted_verifiers: [Pubkey; MAX_ACCEPTED_VERIFIERS], // 128 - Provider's list at creation, empty = config defaults
+}
+
+/// What an escrow paid for, as seen in the x402 HTTP exchange
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct EscrowMetadata {
+    pub payment_hash: Option<[u8; 32]>,   // 1 + 32 - Hash of the x402 payment payload
+    pub endpoint_hash: Option<[u8; 32]>,  // 1 + 32 - Endpoint identifier hash
+    pub quoted_price: Option<u64>,        // 1 + 8 - Price from the 402 response
+    #[max_len(64)]
+    pub memo: String,                     // 4 + 64
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum EscrowStatus {
+    Active,      // Payment locked, awaiting resolution
+    Released,    // Funds released to API (happy path)
+    Disputed,    // Agent disputed quality
+    Resolved,    // Dispute resolved with refund split
+}
+
+/// Entity Reputation - tracks agent/provider performance on-chain
+#[account]
+#[derive(InitSpace)]
+pub struct EntityReputation {
+    pub entity: Pubkey,                   // 32
+    pub entity_type: EntityType,          // 1 + 1
+    pub total_transactions: u64,          // 8
+    pub disputes_filed: u64,              // 8
+    pub disputes_won: u64,                // 8 - Quality <50
+    pub disputes_partial: u64,            // 8 - Quality 50-79
+    pub disputes_lost: u64,               // 8 - Quality >=80
+    pub average_quality_received: u8,     // 1
+    pub reputation_score: u16,            // 2 - 0-1000 score
+    pub created_at: i64,                  // 8
+    pub last_updated: i64,                // 8
+    pub bump: u8,                         // 1
+    pub min_counterparty_score: u16,      // 2 - Floor required of counterparties
+    pub version: u8,                      // 1 - Layout version (0 = pre-versioning)
+    pub quality_histogram: [u32; QUALITY_BUCKETS], // 40 - Quality score deciles
+    pub quality_sum: u64,                 // 8 - Exact sum of quality scores
+    pub quality_samples: u64,             // 8 - Number of quality scores recorded
+    pub window_started_at: i64,           // 8 - Start of the current dispute-rate bucket
+    pub window_transactions: u32,         // 4 - Escrows opened in the current bucket
+    pub window_disputes: u32,             // 4 - Disputes filed in the current bucket
+    pub prev_window_transactions: u32,    // 4
+    pub prev_window_disputes: u32,        // 4
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum EntityType {
+    Agent,
+    Provider,
+}
+
+/// Rate Limiter - prevents spam and abuse
+#[account]
+#[derive(InitSpace)]
+pub struct RateLimiter {
+    pub entity: Pubkey,                   // 32
+    pub verification_level: VerificationLevel, // 1 + 1
+    pub bump: u8,                         // 1
+    pub version: u8,                      // 1 - Layout version (0 = pre-versioning)
+    pub attested_level: VerificationLevel, // 1 - Granted by an attestor
+    pub attestation_expires_at: i64,      // 8 - attested_level lapses at this time
+    pub attestor: Pubkey,                 // 32 - Attestor that may revoke
+    pub hour_tokens: u64,                 // 8 - Token-seconds, see refill_bucket
+    pub day_tokens: u64,                  // 8
+    pub dispute_tokens: u64,              // 8
+    pub tokens_refilled_at: i64,          // 8 - 0 refills every bucket to full
+    pub attestation_nonce: u64,           // 8 - Signed into attestations; bumped on attest and revoke
+}
+
+/// Pre-versioning `RateLimiter` layout, read only by migration
+///
+/// Its fixed hour/day window counters were replaced by token buckets.
+#[derive(AnchorDeserialize)]
+pub struct LegacyRateLimiter {
+    pub entity: Pubkey,
+    pub verification_level: VerificationLevel,
+    pub transactions_last_hour: u16,
+    pub transactions_last_day: u16,
+    pub disputes_last_day: u16,
+    pub last_hour_check: i64,
+    pub last_day_check: i64,
+    pub bump: u8,
+}
+
+/// Ordered from lowest to highest limits. `RateLimiter.verification_level`
+/// only ever holds the stake-backed levels; `Social` and `KYC` come from
+/// attestations.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, InitSpace)]
+pub enum VerificationLevel {
+    Basic,       // Just wallet (low limits)
+    Staked,      // 1+ SOL staked (medium limits)
+    Social,      // Twitter/GitHub linked (high limits)
+    KYC,         // Identity verified (unlimited)
+}
+
+/// Agent Vault - prepaid lamports that fund escrows and receive their refunds
+#[account]
+#[derive(InitSpace)]
+pub struct AgentVault {
+    pub owner: Pubkey,                    // 32
+    pub bump: u8,                         // 1
+    pub version: u8,                      // 1
+}
+
+/// Session Key - a capped, expiring delegate of an agent's wallet
+#[account]
+#[derive(InitSpace)]
+pub struct SessionKey {
+    pub owner: Pubkey,                    // 32 - Agent wallet that authorized the key
+    pub session_key: Pubkey,              // 32
+    pub expires_at: i64,                  // 8
+    pub max_per_escrow: u64,              // 8 - Largest escrow the key may open
+    pub spend_cap: u64,                   // 8 - Lifetime spend across escrows and bonds
+    pub spent: u64,                       // 8
+    pub allowed_providers: [Pubkey; MAX_SESSION_PROVIDERS], // 4 * 32 - Default key = empty slot
+    pub created_at: i64,                  // 8
+    pub bump: u8,                         // 1
+    pub version: u8,                      // 1
+}
+
+/// Program Config - global settings controlled by `authority`
+#[account]
+#[derive(InitSpace)]
+pub struct ProgramConfig {
+    pub authority: Pubkey,                // 32 - Upgrade authority or governance
+    pub bump: u8,                         // 1
+    pub version: u8,                      // 1
+    pub base_dispute_cost: u64,           // 8 - Lamports at 1x
+    pub dispute_cost_tiers: [DisputeCostTier; DISPUTE_COST_TIERS], // 4 * 3
+    pub poor_quality_per_strike: u32,     // 4 - Poor-quality outcomes per strike
+    pub strikes_to_suspend: u8,           // 1
+    pub suspension_duration: i64,         // 8 - Seconds
+    pub appeal_bond: u64,                 // 8 - Lamports locked per appeal
+    pub strike_decay_period: i64,         // 8 - Seconds per strike removed
+    pub treasury: Pubkey,                 // 32 - Receives forfeited appeal bonds
+    pub resolution_period: i64,           // 8 - Seconds a dispute may stay unresolved
+    pub default_refund_percentage: u8,    // 1 - Split applied by settle_unresolved
+    pub staked_level_threshold: u64,      // 8 - Active stake for VerificationLevel::Staked
+    pub default_verifiers: [Pubkey; MAX_ACCEPTED_VERIFIERS], // 4 * 32 - For escrows listing none
+}
+
+impl ProgramConfig {
+    /// Default policy for a new config; forfeits go to `authority` until
+    /// a treasury is set
+    pub fn new(authority: Pubkey, bump: u8) -> Self {
+        Self {
+            authority,
+            bump,
+            version: ProgramConfig::CURRENT_VERSION,
+            base_dispute_cost: BASE_DISPUTE_COST,
+            dispute_cost_tiers: default_dispute_cost_tiers(),
+            poor_quality_per_strike: DEFAULT_POOR_QUALITY_PER_STRIKE,
+            strikes_to_suspend: DEFAULT_STRIKES_TO_SUSPEND,
+            suspension_duration: DEFAULT_SUSPENSION_DURATION,
+            appeal_bond: DEFAULT_APPEAL_BOND,
+            strike_decay_period: DEFAULT_STRIKE_DECAY_PERIOD,
+            treasury: authority,
+            resolution_period: DEFAULT_RESOLUTION_PERIOD,
+            default_refund_percentage: DEFAULT_UNRESOLVED_REFUND_PERCENTAGE,
+            staked_level_threshold: DEFAULT_STAKED_LEVEL_THRESHOLD,
+            default_verifiers: [Pubkey::default(); MAX_ACCEPTED_VERIFIERS],
+        }
+    }
+}
+
+/// One row of the dispute pricing table
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct DisputeCostTier {
+    pub max_rate: u8,                     // 1 - Disputes per 100 transactions
+    pub multiplier: u16,                  // 2 - Applied to base_dispute_cost
+}
+
+/// Attestor - key approved to sign Social/KYC verification
+#[account]
+#[derive(InitSpace)]
+pub struct Attestor {
+    pub attestor: Pubkey,                 // 32
+    pub max_level: VerificationLevel,     // 1
+    pub added_at: i64,                    // 8
+    pub bump: u8,                         // 1
+    pub version: u8,                      // 1
+}
+
+/// Stake Vault - SOL locked by an entity to raise its verification level
+///
+/// The account's lamports are rent + `staked_amount` + `unbonding_amount`.
+#[account]
+#[derive(InitSpace)]
+pub struct StakeVault {
+    pub entity: Pubkey,                   // 32
+    pub staked_amount: u64,               // 8 - Active stake
+    pub unbonding_amount: u64,            // 8 - Pending withdrawal
+    pub unbonding_ends_at: i64,           // 8
+    pub bump: u8,                         // 1
+    pub version: u8,                      // 1
+}
+
+/// Relationship - history between one agent and one API
+#[account]
+#[derive(InitSpace)]
+pub struct Relationship {
+    pub agent: Pubkey,                    // 32
+    pub api: Pubkey,                      // 32
+    pub total_transactions: u64,          // 8 - Settled escrows
+    pub total_volume: u64,                // 8 - Lamports settled
+    pub disputes: u64,                    // 8 - Escrows settled via dispute
+    pub quality_sum: u64,                 // 8 - Sum of dispute quality scores
+    pub first_transaction_at: i64,        // 8
+    pub last_transaction_at: i64,         // 8
+    pub bump: u8,                         // 1
+    pub version: u8,                      // 1
+    pub open_escrows: u32,                // 4 - Unsettled escrows
+    pub locked_value: u64,                // 8 - Lamports in unsettled escrows
+    pub hour_tokens: u64,                 // 8 - Provider hourly bucket, see refill_bucket
+    pub tokens_refilled_at: i64,          // 8
+}
+
+/// Provider Limits - per-agent caps a provider applies to its escrows
+#[account]
+#[derive(InitSpace)]
+pub struct ProviderLimits {
+    pub api: Pubkey,                      // 32
+    pub max_escrows_per_hour: u16,        // 2 - 0 = unlimited
+    pub max_open_escrows: u32,            // 4 - 0 = unlimited
+    pub max_locked_value: u64,            // 8 - 0 = unlimited
+    pub updated_at: i64,                  // 8
+    pub bump: u8,                         // 1
+    pub version: u8,                      // 1
+}
+
+/// Work Agreement - structured scope definition
+#[account]
+#[derive(InitSpace)]
+pub struct WorkAgreement {
+    pub escrow: Pubkey,                   // 32
+    #[max_len(128)]
+    pub query: String,                    // 4 + 128
+    pub required_fields: u8,              // 1 - bitmask or count
+    pub min_records: u32,                 // 4
+    pub max_age_days: u32,                // 4
+    pub min_quality_score: u8,            // 1
+    pub created_at: i64,                  // 8
+    pub bump: u8,                         // 1
+    pub version: u8,                      // 1
+}
+
+/// Provider - published terms for a registered API
+#[account]
+#[derive(InitSpace)]
+pub struct Provider {
+    pub api: Pubkey,                      // 32
+    pub payout: Pubkey,                   // 32 - Receives payments
+    pub endpoint_hash: [u8; 32],          // 32 - Endpoint or service identifier
+    pub default_time_lock: i64,           // 8
+    pub default_price: u64,               // 8 - Minimum escrow amount
+    pub accepted_verifiers: [Pubkey; MAX_ACCEPTED_VERIFIERS], // 4 * 32 - Default key = empty slot
+    pub min_agent_reputation: u16,        // 2
+    pub updated_at: i64,                  // 8
+    pub bump: u8,                         // 1
+    pub version: u8,                      // 1
+    pub dispute_window: i64,              // 8 - 0 = default window
+}
+
+/// Provider Bond - collateral backing refunds above 100%
+#[account]
+#[derive(InitSpace)]
+pub struct ProviderBond {
+    pub api: Pubkey,                      // 32
+    pub balance: u64,                     // 8 - Posted collateral (excludes rent)
+    pub reserved: u64,                    // 8 - Held for open escrows
+    pub bump: u8,                         // 1
+    pub version: u8,                      // 1
+}
+
+/// Provider Penalties - track strikes and suspensions
+#[account]
+#[derive(InitSpace)]
+pub struct ProviderPenalties {
+    pub provider: Pubkey,                 // 32
+    pub strike_count: u8,                 // 1
+    pub suspended: bool,                  // 1
+    pub suspension_end: Option<i64>,      // 1 + 8
+    pub total_refunds_issued: u64,        // 8
+    pub poor_quality_count: u32,          // 4 - Quality <30
+    pub created_at: i64,                  // 8
+    pub last_updated: i64,                // 8
+    pub bump: u8,                         // 1
+    pub version: u8,                      // 1
+    pub last_strike_at: i64,              // 8 - Decay clock for strike_count
+}
+
+/// Appeal - a provider's challenge to its current suspension
+#[account]
+#[derive(InitSpace)]
+pub struct Appeal {
+    pub provider: Pubkey,                 // 32
+    pub evidence_hash: [u8; 32],          // 32 - Hash of off-chain evidence
+    pub bond: u64,                        // 8 - Held until resolution
+    pub suspension_end: i64,              // 8 - Suspension being appealed
+    pub filed_at: i64,                    // 8
+    pub bump: u8,                         // 1
+    pub version: u8,                      // 1
+}
+
+// ============================================================================
+// Account Versioning
+// ============================================================================
+
+/// Account layouts that carry a version byte and can be migrated in place
+///
+/// Layouts are append-only: new fields go at the end of the struct and must
+/// decode from zeroed bytes (`0`, `false`, `None`, first enum variant). An
+/// account written by an older version therefore upgrades by zero-extending
+/// its data, and `upgrade_from` can backfill anything zero does not cover.
+/// Bump `CURRENT_VERSION` whenever a field is appended.
+pub trait Versioned: AccountSerialize + AccountDeserialize + Discriminator + Space {
+    const CURRENT_VERSION: u8;
+
+    fn version(&self) -> u8;
+
+    fn set_version(&mut self, version: u8);
+
+    /// Backfill fields added after `from_version`
+    fn upgrade_from(&mut self, _from_version: u8) {}
+
+    /// Decode account data written at any version, before `upgrade_from`
+    ///
+    /// Missing trailing fields read as zero. Layouts that dropped a field
+    /// override this to read their old layout.
+    fn decode_any_version(data: &[u8]) -> Result<Self> {
+        let mut buf = data.to_vec();
+        if buf.len() < 8 + Self::INIT_SPACE {
+            buf.resize(8 + Self::INIT_SPACE, 0);
+        }
+        Self::try_deserialize(&mut buf.as_slice())
+    }
+
+    /// Decode an account of any version without migrating it
+    ///
+    /// Reads exactly as the account would after `migrate_account`.
+    fn load_any_version(info: &AccountInfo) -> Result<Self> {
+        require_keys_eq!(*info.owner, crate::ID, ErrorCode::AccountOwnedByWrongProgram);
+
+        let mut state = Self::decode_any_version(&info.try_borrow_data()?)?;
+        let from_version = state.version();
+        if from_version < Self::CURRENT_VERSION {
+            state.upgrade_from(from_version);
+        }
+        Ok(state)
+    }
+}
+
+impl Versioned for Escrow {
+    const CURRENT_VERSION: u8 = 1;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+
+    fn upgrade_from(&mut self, _from_version: u8) {
+        // Pre-versioning escrows pay the API directly, and open disputes get
+        // the default resolution period from now on
+        self.payout = self.api;
+        if self.status == EscrowStatus::Disputed {
+            self.resolution_deadline = self.expires_at.saturating_add(DEFAULT_RESOLUTION_PERIOD);
+        }
+    }
+}
+
+impl Versioned for AgentVault {
+    const CURRENT_VERSION: u8 = 1;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+}
+
+impl Versioned for SessionKey {
+    const CURRENT_VERSION: u8 = 1;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+}
+
+impl Versioned for ProviderBond {
+    const CURRENT_VERSION: u8 = 1;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+}
+
+impl Versioned for Provider {
+    const CURRENT_VERSION: u8 = 1;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+}
+
+impl Versioned for EntityReputation {
+    const CURRENT_VERSION: u8 = 1;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+
+    fn upgrade_from(&mut self, _from_version: u8) {
+        // Only the truncated average survives from before the quality
+        // histogram, so seed every past sample into its bucket
+        if self.quality_samples == 0 && self.total_transactions > 0 {
+            let bucket = quality_bucket(self.average_quality_received);
+            self.quality_histogram[bucket] = self.total_transactions.min(u32::MAX as u64) as u32;
+            self.quality_sum = (self.average_quality_received as u64)
+                .saturating_mul(self.total_transactions);
+            self.quality_samples = self.total_transactions;
+        }
+    }
+}
+
+impl Versioned for Relationship {
+    const CURRENT_VERSION: u8 = 1;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+}
+
+impl Versioned for StakeVault {
+    const CURRENT_VERSION: u8 = 1;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+}
+
+impl Versioned for ProgramConfig {
+    const CURRENT_VERSION: u8 = 1;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+
+}
+
+impl Versioned for Attestor {
+    const CURRENT_VERSION: u8 = 1;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+}
+
+impl Versioned for ProviderPenalties {
+    const CURRENT_VERSION: u8 = 1;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+
+    fn upgrade_from(&mut self, _from_version: u8) {
+        // Strike decay starts its clock at the last update
+        self.last_strike_at = self.last_updated;
+    }
+}
+
+impl Versioned for WorkAgreement {
+    const CURRENT_VERSION: u8 = 1;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+}
+
+impl Versioned for Appeal {
+    const CURRENT_VERSION: u8 = 1;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+}
+
+impl Versioned for ProviderLimits {
+    const CURRENT_VERSION: u8 = 1;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+}
+
+impl Versioned for RateLimiter {
+    const CURRENT_VERSION: u8 = 1;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+
+    /// Pre-versioning limiters carry window counters in the middle of the
+    /// layout, so they are read field by field rather than zero-extended.
+    /// Their buckets start empty of history, which refills them to full.
+    fn decode_any_version(data: &[u8]) -> Result<Self> {
+        if data.len() >= 8 + Self::INIT_SPACE {
+            return Self::try_deserialize(&mut &data[..]);
+        }
+        require!(data.starts_with(Self::DISCRIMINATOR), ErrorCode::AccountDiscriminatorMismatch);
+
+        let legacy = LegacyRateLimiter::deserialize(&mut &data[Self::DISCRIMINATOR.len()..])
+            .map_err(|_| error!(ErrorCode::AccountDidNotDeserialize))?;
+        Ok(RateLimiter {
+            entity: legacy.entity,
+            verification_level: legacy.verification_level,
+            bump: legacy.bump,
+            version: 0,
+            attested_level: VerificationLevel::Basic,
+            attestation_expires_at: 0,
+            attestor: Pubkey::default(),
+            hour_tokens: 0,
+            day_tokens: 0,
+            dispute_tokens: 0,
+            tokens_refilled_at: 0,
+            attestation_nonce: 0,
+        })
+    }
+}
+
+/// Reject writes to an account that has not been migrated yet
+///
+/// Writing back an old-version account would stamp the current layout over
+/// it without running `upgrade_from`. Typed accounts check this with an
+/// `AccountNotMigrated` constraint instead.
+pub(crate) fn require_current_version<T: Versioned>(account: &T) -> Result<()> {
+    require!(
+        account.version() == T::CURRENT_VERSION,
+        EscrowError::AccountNotMigrated
+    );
+    Ok(())
+}
+
+/// Decode old account data and bring it to the current version
+///
+/// Returns the upgraded state and the version it was written at.
+pub(crate) fn upgrade_account<T: Versioned>(data: &[u8]) -> Result<(T, u8)> {
+    require!(data.starts_with(T::DISCRIMINATOR), ErrorCode::AccountDiscriminatorMismatch);
+
+    let mut state = T::decode_any_version(data)?;
+    let from_version = state.version();
+    require!(
+        from_version < T::CURRENT_VERSION,
+        EscrowError::AccountAlreadyMigrated
+    );
+
+    state.upgrade_from(from_version);
+    state.set_version(T::CURRENT_VERSION);
+    Ok((state, from_version))
+}
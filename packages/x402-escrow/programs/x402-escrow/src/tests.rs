@@ -0,0 +1,868 @@
+use super::*;
+use anchor_lang::solana_program::{program_stubs, sysvar::instructions::ID as INSTRUCTIONS_ID};
+use anchor_lang::system_program;
+use std::collections::BTreeSet;
+
+// Arbitrary timestamp; HOUR_BOUNDARY is an exact multiple of both windows
+const HOUR_BOUNDARY: i64 = 1_700_006_400;
+
+/// Account as `init` leaves it, before the handler fills it in
+fn zeroed<T: AccountDeserialize + Space>() -> T {
+    T::try_deserialize_unchecked(&mut &vec![0u8; 8 + T::INIT_SPACE][..]).unwrap()
+}
+
+fn config() -> ProgramConfig {
+    ProgramConfig::new(Pubkey::new_unique(), 255)
+}
+
+/// Backing storage for an `AccountInfo` passed to `try_accounts`
+struct TestAccount {
+    key: Pubkey,
+    owner: Pubkey,
+    lamports: u64,
+    data: Vec<u8>,
+    is_signer: bool,
+    executable: bool,
+}
+
+impl TestAccount {
+    fn wallet(key: Pubkey) -> Self {
+        TestAccount {
+            key,
+            owner: system_program::ID,
+            lamports: 1_000_000_000,
+            data: Vec::new(),
+            is_signer: false,
+            executable: false,
+        }
+    }
+
+    fn state<T: AccountSerialize + Space>(key: Pubkey, state: &T) -> Self {
+        let mut data = Vec::new();
+        state.try_serialize(&mut data).unwrap();
+        data.resize(8 + T::INIT_SPACE, 0);
+        TestAccount { owner: crate::ID, data, ..Self::wallet(key) }
+    }
+}
+
+fn account_infos(accounts: &mut [TestAccount]) -> Vec<AccountInfo<'_>> {
+    accounts
+        .iter_mut()
+        .map(|a| AccountInfo::new(&a.key, a.is_signer, true, &mut a.lamports, &mut a.data, &a.owner, a.executable, 0))
+        .collect()
+}
+
+/// Serves the default `Rent` to account constraints that read the sysvar
+struct RentSysvar;
+
+impl program_stubs::SyscallStubs for RentSysvar {
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        anchor_lang::solana_program::entrypoint::SUCCESS
+    }
+}
+
+fn pda(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &crate::ID)
+}
+
+/// Accounts for `resolve_dispute` on an escrow between `agent` and
+/// `escrow_api`, passing `api` (and the PDAs it seeds) as the provider
+fn resolve_dispute_accounts(agent: Pubkey, escrow_api: Pubkey, api: Pubkey) -> Vec<TestAccount> {
+    let transaction_id = "tx_resolve";
+    let (escrow_key, escrow_bump) = pda(&[b"escrow", transaction_id.as_bytes()]);
+    let escrow = Escrow {
+        agent,
+        api: escrow_api,
+        payout: escrow_api,
+        status: EscrowStatus::Disputed,
+        transaction_id: transaction_id.to_string(),
+        bump: escrow_bump,
+        version: Escrow::CURRENT_VERSION,
+        ..zeroed()
+    };
+    let reputation = |entity: Pubkey| {
+        let (key, bump) = pda(&[b"reputation", entity.as_ref()]);
+        let state = EntityReputation { entity, bump, version: EntityReputation::CURRENT_VERSION, ..zeroed() };
+        TestAccount::state(key, &state)
+    };
+    let (relationship_key, relationship_bump) = pda(&[b"relationship", agent.as_ref(), api.as_ref()]);
+    let relationship = Relationship {
+        agent,
+        api,
+        bump: relationship_bump,
+        version: Relationship::CURRENT_VERSION,
+        ..zeroed()
+    };
+    let (penalties_key, penalties_bump) = pda(&[b"penalties", api.as_ref()]);
+    let penalties = ProviderPenalties {
+        provider: api,
+        bump: penalties_bump,
+        version: ProviderPenalties::CURRENT_VERSION,
+        ..zeroed()
+    };
+    let (config_key, config_bump) = pda(&[b"config"]);
+
+    vec![
+        TestAccount::state(escrow_key, &escrow),
+        TestAccount::wallet(agent),
+        TestAccount::wallet(api),
+        TestAccount::wallet(escrow_api),
+        TestAccount::wallet(pda(&[b"vault", agent.as_ref()]).0),
+        TestAccount::wallet(pda(&[b"bond", escrow_api.as_ref()]).0),
+        TestAccount::wallet(Pubkey::new_unique()),
+        TestAccount::wallet(INSTRUCTIONS_ID),
+        reputation(agent),
+        reputation(api),
+        TestAccount::state(relationship_key, &relationship),
+        TestAccount::state(penalties_key, &penalties),
+        TestAccount::state(config_key, &ProgramConfig::new(Pubkey::new_unique(), config_bump)),
+        TestAccount { is_signer: true, ..TestAccount::wallet(Pubkey::new_unique()) },
+        TestAccount { executable: true, ..TestAccount::wallet(system_program::ID) },
+    ]
+}
+
+fn consume_n(limiter: &mut RateLimiter, n: u16, now: i64) -> u16 {
+    (0..n).filter(|_| consume_rate_limit(limiter, now).is_ok()).count() as u16
+}
+
+fn record_activity(reputation: &mut EntityReputation, transactions: u32, disputes: u32, now: i64) {
+    (0..transactions).for_each(|_| record_window_transaction(reputation, now));
+    (0..disputes).for_each(|_| record_window_dispute(reputation, now));
+}
+
+#[test]
+fn past_dispute_abuse_ages_out() {
+    let config = config();
+    let mut reputation = zeroed::<EntityReputation>();
+    record_activity(&mut reputation, 10, 8, HOUR_BOUNDARY);
+    assert_eq!(calculate_dispute_cost(&reputation, &config, HOUR_BOUNDARY), 10 * BASE_DISPUTE_COST);
+
+    // A clean month afterwards pulls the rate down as the old bucket fades
+    record_activity(&mut reputation, 20, 0, HOUR_BOUNDARY + DISPUTE_RATE_WINDOW);
+    assert_eq!(
+        calculate_dispute_cost(&reputation, &config, HOUR_BOUNDARY + DISPUTE_RATE_WINDOW + DISPUTE_RATE_WINDOW / 2),
+        BASE_DISPUTE_COST
+    );
+
+    // Two windows later the abuse no longer counts at all
+    assert_eq!(rolling_dispute_rate(&reputation, HOUR_BOUNDARY + 3 * DISPUTE_RATE_WINDOW), None);
+}
+
+#[test]
+fn new_dispute_abuse_is_priced_immediately() {
+    let config = config();
+    // Long clean history that would dilute a lifetime rate
+    let mut reputation = EntityReputation {
+        total_transactions: 1_000,
+        disputes_filed: 10,
+        ..zeroed()
+    };
+    record_activity(&mut reputation, 50, 0, HOUR_BOUNDARY);
+
+    let now = HOUR_BOUNDARY + 2 * DISPUTE_RATE_WINDOW;
+    record_activity(&mut reputation, 5, 3, now);
+    assert_eq!(rolling_dispute_rate(&reputation, now), Some(60));
+    assert_eq!(calculate_dispute_cost(&reputation, &config, now), 5 * BASE_DISPUTE_COST);
+}
+
+#[test]
+fn dispute_cost_uses_configured_tiers() {
+    let config = ProgramConfig {
+        base_dispute_cost: 2_000_000,
+        dispute_cost_tiers: [
+            DisputeCostTier { max_rate: 10, multiplier: 1 },
+            DisputeCostTier { max_rate: 25, multiplier: 3 },
+            DisputeCostTier { max_rate: 50, multiplier: 6 },
+            DisputeCostTier { max_rate: 100, multiplier: 20 },
+        ],
+        ..config()
+    };
+
+    let mut reputation = zeroed::<EntityReputation>();
+    assert_eq!(calculate_dispute_cost(&reputation, &config, HOUR_BOUNDARY), 2_000_000);
+
+    record_activity(&mut reputation, 10, 2, HOUR_BOUNDARY);
+    assert_eq!(calculate_dispute_cost(&reputation, &config, HOUR_BOUNDARY), 6_000_000);
+
+    // Rates above 100 (disputes on escrows from an earlier bucket) use the last tier
+    assert_eq!(dispute_cost_multiplier(&config.dispute_cost_tiers, 250), 20);
+}
+
+#[test]
+fn poor_quality_outcomes_add_strikes_then_suspend() {
+    let config = config();
+    let mut penalties = zeroed::<ProviderPenalties>();
+
+    // Acceptable quality only accumulates refunds
+    record_provider_outcome(&mut penalties, &config, 400, POOR_QUALITY_THRESHOLD, HOUR_BOUNDARY);
+    assert_eq!((penalties.poor_quality_count, penalties.strike_count), (0, 0));
+    assert_eq!(penalties.total_refunds_issued, 400);
+
+    for _ in 0..DEFAULT_POOR_QUALITY_PER_STRIKE {
+        record_provider_outcome(&mut penalties, &config, 900, 10, HOUR_BOUNDARY);
+    }
+    assert_eq!(penalties.strike_count, 1);
+    assert!(!provider_is_suspended(&penalties, HOUR_BOUNDARY));
+
+    let poor_outcomes = DEFAULT_POOR_QUALITY_PER_STRIKE * (DEFAULT_STRIKES_TO_SUSPEND as u32 - 1);
+    for _ in 0..poor_outcomes {
+        record_provider_outcome(&mut penalties, &config, 900, 10, HOUR_BOUNDARY);
+    }
+    assert!(provider_is_suspended(&penalties, HOUR_BOUNDARY));
+    assert_eq!(penalties.suspension_end, Some(HOUR_BOUNDARY + DEFAULT_SUSPENSION_DURATION));
+    assert_eq!(penalties.strike_count, 0);
+}
+
+#[test]
+fn strikes_decay_one_per_period() {
+    let mut penalties = ProviderPenalties {
+        strike_count: 2,
+        last_strike_at: HOUR_BOUNDARY,
+        ..zeroed()
+    };
+
+    decay_strikes(&mut penalties, DEFAULT_STRIKE_DECAY_PERIOD, HOUR_BOUNDARY + DEFAULT_STRIKE_DECAY_PERIOD - 1);
+    assert_eq!(penalties.strike_count, 2);
+
+    // Partial progress past the first removal carries over
+    let now = HOUR_BOUNDARY + DEFAULT_STRIKE_DECAY_PERIOD + DEFAULT_STRIKE_DECAY_PERIOD / 2;
+    decay_strikes(&mut penalties, DEFAULT_STRIKE_DECAY_PERIOD, now);
+    assert_eq!(penalties.strike_count, 1);
+    decay_strikes(&mut penalties, DEFAULT_STRIKE_DECAY_PERIOD, HOUR_BOUNDARY + 2 * DEFAULT_STRIKE_DECAY_PERIOD);
+    assert_eq!(penalties.strike_count, 0);
+}
+
+#[test]
+fn decayed_strikes_delay_suspension() {
+    let config = config();
+    let mut penalties = zeroed::<ProviderPenalties>();
+    let per_strike = DEFAULT_POOR_QUALITY_PER_STRIKE;
+
+    // Two strikes, then a long quiet period before the next poor outcomes
+    for _ in 0..2 * per_strike {
+        record_provider_outcome(&mut penalties, &config, 0, 0, HOUR_BOUNDARY);
+    }
+    assert_eq!(penalties.strike_count, 2);
+
+    let later = HOUR_BOUNDARY + 2 * DEFAULT_STRIKE_DECAY_PERIOD;
+    for _ in 0..per_strike {
+        record_provider_outcome(&mut penalties, &config, 0, 0, later);
+    }
+    assert_eq!(penalties.strike_count, 1);
+    assert!(!provider_is_suspended(&penalties, later));
+}
+
+#[test]
+fn suspension_lapses_after_duration() {
+    let config = config();
+    let mut penalties = ProviderPenalties {
+        suspended: true,
+        suspension_end: Some(HOUR_BOUNDARY),
+        ..zeroed()
+    };
+
+    assert!(provider_is_suspended(&penalties, HOUR_BOUNDARY - 1));
+    assert!(!provider_is_suspended(&penalties, HOUR_BOUNDARY));
+
+    // The next recorded outcome clears the stale flag
+    record_provider_outcome(&mut penalties, &config, 0, 90, HOUR_BOUNDARY);
+    assert!(!penalties.suspended);
+    assert_eq!(penalties.suspension_end, None);
+}
+
+#[test]
+fn penalty_account_is_created_on_first_resolution() {
+    let api = Pubkey::new_unique();
+    let mut penalties = zeroed::<ProviderPenalties>();
+    prepare_provider_penalties(&mut penalties, api, 254, HOUR_BOUNDARY).unwrap();
+    assert_eq!(penalties.provider, api);
+    assert_eq!(penalties.version, ProviderPenalties::CURRENT_VERSION);
+    assert!(!provider_is_suspended(&penalties, HOUR_BOUNDARY));
+
+    // Existing accounts are left alone but must be migrated
+    penalties.strike_count = 2;
+    prepare_provider_penalties(&mut penalties, Pubkey::new_unique(), 1, HOUR_BOUNDARY + 1).unwrap();
+    assert_eq!((penalties.provider, penalties.strike_count, penalties.bump), (api, 2, 254));
+
+    penalties.version = 0;
+    assert!(prepare_provider_penalties(&mut penalties, api, 254, HOUR_BOUNDARY).is_err());
+}
+
+#[test]
+fn resolution_rejects_a_provider_other_than_the_escrows() {
+    program_stubs::set_syscall_stubs(Box::new(RentSysvar));
+    let (agent, api) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    let mut accounts = resolve_dispute_accounts(agent, api, api);
+    let infos = account_infos(&mut accounts);
+    let mut bumps = ResolveDisputeBumps::default();
+    let result = ResolveDispute::try_accounts(&crate::ID, &mut &infos[..], &[], &mut bumps, &mut BTreeSet::new());
+    assert!(result.is_ok());
+
+    // Strikes and reputation must not land on an unrelated provider
+    let mut accounts = resolve_dispute_accounts(agent, api, Pubkey::new_unique());
+    let infos = account_infos(&mut accounts);
+    let mut bumps = ResolveDisputeBumps::default();
+    let result = ResolveDispute::try_accounts(&crate::ID, &mut &infos[..], &[], &mut bumps, &mut BTreeSet::new());
+    assert_eq!(result.err(), Some(EscrowError::Unauthorized.into()));
+}
+
+#[test]
+fn only_config_authority_updates_reputation() {
+    let authority = Pubkey::new_unique();
+    let entity = Pubkey::new_unique();
+    let (reputation_key, reputation_bump) = pda(&[b"reputation", entity.as_ref()]);
+    let reputation = EntityReputation {
+        entity,
+        bump: reputation_bump,
+        version: EntityReputation::CURRENT_VERSION,
+        ..zeroed()
+    };
+    let (config_key, config_bump) = pda(&[b"config"]);
+    let accounts = |signer: Pubkey| {
+        vec![
+            TestAccount::state(reputation_key, &reputation),
+            TestAccount::state(config_key, &ProgramConfig::new(authority, config_bump)),
+            TestAccount { is_signer: true, ..TestAccount::wallet(signer) },
+        ]
+    };
+
+    let mut signed = accounts(authority);
+    let infos = account_infos(&mut signed);
+    let result = UpdateReputation::try_accounts(
+        &crate::ID,
+        &mut &infos[..],
+        &[],
+        &mut UpdateReputationBumps::default(),
+        &mut BTreeSet::new(),
+    );
+    assert!(result.is_ok());
+
+    let mut unrelated = accounts(Pubkey::new_unique());
+    let infos = account_infos(&mut unrelated);
+    let result = UpdateReputation::try_accounts(
+        &crate::ID,
+        &mut &infos[..],
+        &[],
+        &mut UpdateReputationBumps::default(),
+        &mut BTreeSet::new(),
+    );
+    assert_eq!(result.err(), Some(EscrowError::Unauthorized.into()));
+}
+
+#[test]
+fn dispute_quote_reads_config_only_when_passed() {
+    assert_eq!(quote_config(&[]).unwrap().base_dispute_cost, BASE_DISPUTE_COST);
+
+    let (config_key, config_bump) = pda(&[b"config"]);
+    let config = ProgramConfig {
+        base_dispute_cost: 3 * BASE_DISPUTE_COST,
+        ..ProgramConfig::new(Pubkey::new_unique(), config_bump)
+    };
+    let mut accounts = vec![
+        TestAccount::state(config_key, &config),
+        TestAccount::state(Pubkey::new_unique(), &config),
+    ];
+    let infos = account_infos(&mut accounts);
+    assert_eq!(quote_config(&infos[..1]).unwrap().base_dispute_cost, 3 * BASE_DISPUTE_COST);
+
+    // A config-shaped account at another address can't reprice the quote
+    assert!(quote_config(&infos[1..]).is_err());
+}
+
+#[test]
+fn verifier_message_commits_to_agreement() {
+    let mut escrow = Escrow { transaction_id: "tx_abc123".to_string(), ..zeroed() };
+    assert_eq!(verifier_message(&escrow, 65, 35), "tx_abc123:65:35");
+
+    let mut agreement_hash = [0u8; 32];
+    agreement_hash[0] = 0xab;
+    agreement_hash[31] = 0x01;
+    escrow.agreement_hash = Some(agreement_hash);
+    let expected = format!("tx_abc123:65:35:ab{}01", "00".repeat(30));
+    assert_eq!(verifier_message(&escrow, 65, 35), expected);
+
+    escrow.response_hash = Some([0xff; 32]);
+    let expected = format!("{}:{}", expected, "ff".repeat(32));
+    assert_eq!(verifier_message(&escrow, 65, 35), expected);
+
+    // The refund is signed on every path, not just penalties
+    assert_ne!(verifier_message(&escrow, 65, 0), expected);
+    assert!(verifier_message(&escrow, 65, 150).starts_with("tx_abc123:65:150:"));
+}
+
+#[test]
+fn bond_reservations_cover_penalties_and_block_withdrawal() {
+    let mut bond = ProviderBond { balance: 1_000, ..zeroed() };
+    reserve_bond(&mut bond, 600).unwrap();
+    assert_eq!((bond.balance, bond.reserved), (1_000, 600));
+    assert!(reserve_bond(&mut bond, 401).is_err());
+
+    // Only the unreserved 400 can be withdrawn
+    assert!(withdraw_unreserved_bond(&mut bond, 401).is_err());
+    withdraw_unreserved_bond(&mut bond, 400).unwrap();
+    assert_eq!((bond.balance, bond.reserved), (600, 600));
+    assert!(withdraw_unreserved_bond(&mut bond, 1).is_err());
+
+    // A penalty is paid from the reservation it releases
+    assert!(release_bond(&mut bond, 600, 601).is_err());
+    release_bond(&mut bond, 600, 250).unwrap();
+    assert_eq!((bond.balance, bond.reserved), (350, 0));
+    withdraw_unreserved_bond(&mut bond, 350).unwrap();
+    assert_eq!(bond.balance, 0);
+}
+
+#[test]
+fn verifiers_are_checked_against_escrow_snapshot() {
+    let verifier = Pubkey::new_unique();
+    let mut config = config();
+    let mut escrow = zeroed::<Escrow>();
+
+    // No list anywhere accepts nobody, not even the default key
+    assert!(require_accepted_verifier(&escrow, &config, &verifier).is_err());
+    assert!(require_accepted_verifier(&escrow, &config, &Pubkey::default()).is_err());
+
+    // An escrow listing none is judged by the config's defaults
+    config.default_verifiers[0] = verifier;
+    assert!(require_accepted_verifier(&escrow, &config, &verifier).is_ok());
+    assert!(require_accepted_verifier(&escrow, &config, &Pubkey::new_unique()).is_err());
+
+    // Whatever the provider lists later, the escrow keeps its own list
+    let listed = Pubkey::new_unique();
+    escrow.accepted_verifiers[1] = listed;
+    assert!(require_accepted_verifier(&escrow, &config, &listed).is_ok());
+    assert!(require_accepted_verifier(&escrow, &config, &verifier).is_err());
+}
+
+#[test]
+fn dispute_window_starts_at_delivery() {
+    let mut escrow = Escrow { expires_at: HOUR_BOUNDARY + MAX_TIME_LOCK, ..zeroed() };
+    assert_eq!(dispute_window_end(&escrow), escrow.expires_at);
+
+    escrow.delivered_at = HOUR_BOUNDARY;
+    assert_eq!(dispute_window_end(&escrow), HOUR_BOUNDARY + DISPUTE_WINDOW);
+}
+
+#[test]
+fn dispute_window_is_separate_from_time_lock() {
+    // Dispute within 2h, auto-release at 24h
+    let mut escrow = Escrow {
+        created_at: HOUR_BOUNDARY,
+        expires_at: HOUR_BOUNDARY + RATE_LIMIT_DAY,
+        dispute_window: 2 * RATE_LIMIT_HOUR,
+        ..zeroed()
+    };
+
+    assert_eq!(dispute_window_end(&escrow), HOUR_BOUNDARY + 2 * RATE_LIMIT_HOUR);
+    assert_eq!(auto_release_at(&escrow), escrow.expires_at);
+
+    // Delivery can only restart the window while it is open
+    assert_eq!(delivery_deadline(&escrow), HOUR_BOUNDARY + 2 * RATE_LIMIT_HOUR);
+    escrow.delivered_at = HOUR_BOUNDARY + RATE_LIMIT_HOUR;
+    assert_eq!(dispute_window_end(&escrow), HOUR_BOUNDARY + 3 * RATE_LIMIT_HOUR);
+    assert_eq!(auto_release_at(&escrow), escrow.expires_at);
+
+    // Without a provider window, delivery is open until expiry and may
+    // hold back auto-release
+    let mut escrow = Escrow { dispute_window: 0, delivered_at: 0, ..escrow };
+    assert_eq!(delivery_deadline(&escrow), escrow.expires_at);
+    escrow.delivered_at = escrow.expires_at - RATE_LIMIT_HOUR;
+    assert_eq!(auto_release_at(&escrow), escrow.delivered_at + DISPUTE_WINDOW);
+}
+
+#[test]
+fn sla_refund_follows_table() {
+    assert_eq!(sla_refund_percentage(500, 500, 10_000), 0);
+    assert_eq!(sla_refund_percentage(500, 501, 10_000), 25);
+    assert_eq!(sla_refund_percentage(500, 1_500, 9_999), 50);
+    assert_eq!(sla_refund_percentage(500, 400, 8_000), 75);
+    assert_eq!(sla_refund_percentage(500, 5_001, 10_000), 100);
+    assert_eq!(sla_refund_percentage(500, 100, 4_999), 100);
+
+    // Availability-only SLA ignores latency
+    assert_eq!(sla_refund_percentage(0, u32::MAX, 9_900), 0);
+}
+
+#[test]
+fn sla_message_commits_to_terms() {
+    let mut escrow = Escrow {
+        transaction_id: "tx_abc123".to_string(),
+        sla_max_latency_ms: 500,
+        sla_deadline: HOUR_BOUNDARY,
+        ..zeroed()
+    };
+    let expected = format!("sla:tx_abc123:500:{}:420:9990", HOUR_BOUNDARY);
+    assert_eq!(sla_message(&escrow, 420, 9_990), expected);
+
+    escrow.agreement_hash = Some([0xab; 32]);
+    escrow.response_hash = Some([0xff; 32]);
+    let expected = format!("{}:{}:{}", expected, "ab".repeat(32), "ff".repeat(32));
+    assert_eq!(sla_message(&escrow, 420, 9_990), expected);
+}
+
+#[test]
+fn legacy_disputed_escrow_gets_resolution_deadline() {
+    let mut disputed = Escrow {
+        status: EscrowStatus::Disputed,
+        expires_at: HOUR_BOUNDARY,
+        ..zeroed()
+    };
+
+    disputed.upgrade_from(0);
+    assert_eq!(disputed.resolution_deadline, HOUR_BOUNDARY + DEFAULT_RESOLUTION_PERIOD);
+
+    // Active escrows get their deadline from mark_disputed instead
+    let mut active = Escrow { api: Pubkey::new_unique(), ..zeroed() };
+    active.upgrade_from(0);
+    assert_eq!(active.resolution_deadline, 0);
+    assert_eq!(active.payout, active.api);
+}
+
+#[test]
+fn legacy_escrow_migrates_from_pre_versioning_layout() {
+    // Pre-versioning accounts were allocated at the old size: the fields
+    // up to refund_percentage, with room for a 64-byte transaction id
+    let (agent, api) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut data = Escrow::DISCRIMINATOR.to_vec();
+    (agent, api, 5_000_000u64, EscrowStatus::Disputed, HOUR_BOUNDARY, HOUR_BOUNDARY + RATE_LIMIT_DAY)
+        .serialize(&mut data)
+        .unwrap();
+    ("tx_legacy".to_string(), 254u8, None::<u8>, None::<u8>).serialize(&mut data).unwrap();
+    data.resize(8 + 32 + 32 + 8 + 1 + 8 + 8 + (4 + 64) + 1 + 2 + 2, 0);
+
+    let (escrow, from_version) = upgrade_account::<Escrow>(&data).unwrap();
+    assert_eq!(from_version, 0);
+    assert_eq!(escrow.version, Escrow::CURRENT_VERSION);
+    assert_eq!((escrow.agent, escrow.api, escrow.payout), (agent, api, api));
+    assert_eq!((escrow.amount, escrow.bump, escrow.transaction_id.as_str()), (5_000_000, 254, "tx_legacy"));
+    assert_eq!(escrow.resolution_deadline, HOUR_BOUNDARY + RATE_LIMIT_DAY + DEFAULT_RESOLUTION_PERIOD);
+
+    // Migrating twice is rejected
+    let mut migrated = Vec::new();
+    escrow.try_serialize(&mut migrated).unwrap();
+    assert!(upgrade_account::<Escrow>(&migrated).is_err());
+}
+
+#[test]
+fn writes_require_migrated_accounts() {
+    let mut legacy = Escrow { version: Escrow::CURRENT_VERSION - 1, ..zeroed() };
+    assert!(require_current_version(&legacy).is_err());
+
+    legacy.upgrade_from(legacy.version);
+    legacy.set_version(Escrow::CURRENT_VERSION);
+    assert!(require_current_version(&legacy).is_ok());
+}
+
+#[test]
+fn escrow_with_full_metadata_fits_account() {
+    let full = Escrow {
+        transaction_id: "t".repeat(64),
+        agreement_hash: Some([1; 32]),
+        response_hash: Some([2; 32]),
+        refund_percentage: Some(100),
+        quality_score: Some(100),
+        metadata: Some(EscrowMetadata {
+            payment_hash: Some([3; 32]),
+            endpoint_hash: Some([4; 32]),
+            quoted_price: Some(MAX_ESCROW_AMOUNT),
+            memo: "m".repeat(MAX_MEMO_LEN),
+        }),
+        ..zeroed()
+    };
+
+    assert_eq!(full.try_to_vec().unwrap().len(), Escrow::INIT_SPACE);
+}
+
+#[test]
+fn hour_boundary_burst_is_rejected() {
+    assert_eq!(HOUR_BOUNDARY % RATE_LIMIT_DAY, 0);
+    let mut limiter = RateLimiter { verification_level: VerificationLevel::Staked, ..zeroed() };
+
+    // Spend the full hourly allowance one second before the boundary
+    assert_eq!(consume_n(&mut limiter, 10, HOUR_BOUNDARY - 1), 10);
+    assert!(consume_rate_limit(&mut limiter, HOUR_BOUNDARY - 1).is_err());
+
+    // A fixed window would hand out 10 more here; the bucket has refilled
+    // for two seconds only
+    assert_eq!(consume_n(&mut limiter, 10, HOUR_BOUNDARY + 1), 0);
+}
+
+#[test]
+fn hour_bucket_refills_continuously() {
+    let mut limiter = RateLimiter { verification_level: VerificationLevel::Staked, ..zeroed() };
+    assert_eq!(consume_n(&mut limiter, 10, HOUR_BOUNDARY), 10);
+
+    // Staked refills 10 per hour: one token every 360 seconds
+    assert!(consume_rate_limit(&mut limiter, HOUR_BOUNDARY + 359).is_err());
+    assert!(consume_rate_limit(&mut limiter, HOUR_BOUNDARY + 360).is_ok());
+    assert!(consume_rate_limit(&mut limiter, HOUR_BOUNDARY + 360).is_err());
+
+    // Capacity caps accumulation after a long idle period
+    assert_eq!(consume_n(&mut limiter, 20, HOUR_BOUNDARY + 10 * 3600), 10);
+}
+
+#[test]
+fn frequent_refills_do_not_lose_precision() {
+    let mut limiter = RateLimiter { verification_level: VerificationLevel::Basic, ..zeroed() };
+    assert!(consume_rate_limit(&mut limiter, HOUR_BOUNDARY).is_ok());
+
+    // Failed attempts every second must not stall the refill
+    for t in 1..3600 {
+        refill_rate_limit_buckets(&mut limiter, HOUR_BOUNDARY + t);
+        assert!(limiter.hour_tokens < RATE_LIMIT_HOUR as u64);
+    }
+    assert!(consume_rate_limit(&mut limiter, HOUR_BOUNDARY + 3600).is_ok());
+}
+
+#[test]
+fn daily_limit_binds_across_hours() {
+    let mut limiter = RateLimiter { verification_level: VerificationLevel::Basic, ..zeroed() };
+
+    // Basic allows 1/hour and 10/day
+    let mut allowed = 0;
+    for hour in 0..24 {
+        allowed += consume_n(&mut limiter, 1, HOUR_BOUNDARY + hour * 3600);
+    }
+    // 10 from the full day bucket plus what refilled in 23 hours
+    assert_eq!(allowed, 10 + (23 * 10 / 24) as u16);
+}
+
+#[test]
+fn day_boundary_dispute_burst_is_rejected() {
+    let day_boundary = HOUR_BOUNDARY;
+    let mut limiter = RateLimiter { verification_level: VerificationLevel::Basic, ..zeroed() };
+
+    // Basic allows 3 disputes/day
+    for remaining in (0..3).rev() {
+        let (left, limit) = consume_dispute_limit(&mut limiter, day_boundary - 1).unwrap();
+        assert_eq!((left, limit), (remaining, 3));
+    }
+    assert!(consume_dispute_limit(&mut limiter, day_boundary - 1).is_err());
+    assert!(consume_dispute_limit(&mut limiter, day_boundary + 1).is_err());
+
+    // One dispute refills every 8 hours
+    assert!(consume_dispute_limit(&mut limiter, day_boundary - 1 + 8 * 3600).is_ok());
+}
+
+#[test]
+fn relationship_counts_settlements_and_disputes() {
+    let mut pair = zeroed::<Relationship>();
+    assert_eq!(relationship_average_quality(&pair), None);
+
+    record_relationship_settlement(&mut pair, 2_000_000, None, HOUR_BOUNDARY);
+    record_relationship_settlement(&mut pair, 1_000_000, Some(40), HOUR_BOUNDARY + 60);
+    record_relationship_settlement(&mut pair, 3_000_000, Some(81), HOUR_BOUNDARY + 120);
+
+    assert_eq!(pair.total_transactions, 3);
+    assert_eq!(pair.total_volume, 6_000_000);
+    assert_eq!(pair.disputes, 2);
+    assert_eq!(pair.quality_sum, 121);
+    assert_eq!(relationship_average_quality(&pair), Some(61));
+    assert_eq!(pair.first_transaction_at, HOUR_BOUNDARY);
+    assert_eq!(pair.last_transaction_at, HOUR_BOUNDARY + 120);
+}
+
+#[test]
+fn provider_limits_cap_open_escrows_and_locked_value() {
+    let limits = ProviderLimits {
+        max_escrows_per_hour: 0,
+        max_open_escrows: 2,
+        max_locked_value: 5_000_000,
+        ..zeroed()
+    };
+    let mut pair = zeroed::<Relationship>();
+
+    assert!(consume_provider_limit(&mut pair, Some(&limits), 2_000_000, HOUR_BOUNDARY).is_ok());
+    // Would exceed max_locked_value
+    assert!(consume_provider_limit(&mut pair, Some(&limits), 4_000_000, HOUR_BOUNDARY).is_err());
+    assert!(consume_provider_limit(&mut pair, Some(&limits), 3_000_000, HOUR_BOUNDARY).is_ok());
+    // Would exceed max_open_escrows
+    assert!(consume_provider_limit(&mut pair, Some(&limits), 1_000_000, HOUR_BOUNDARY).is_err());
+    assert_eq!((pair.open_escrows, pair.locked_value), (2, 5_000_000));
+
+    // Settlement frees capacity
+    record_relationship_settlement(&mut pair, 3_000_000, None, HOUR_BOUNDARY);
+    assert!(consume_provider_limit(&mut pair, Some(&limits), 1_000_000, HOUR_BOUNDARY).is_ok());
+}
+
+#[test]
+fn provider_hourly_limit_is_per_pair() {
+    let limits = ProviderLimits {
+        max_escrows_per_hour: 2,
+        max_open_escrows: 0,
+        max_locked_value: 0,
+        ..zeroed()
+    };
+    let mut first = zeroed::<Relationship>();
+    let mut second = zeroed::<Relationship>();
+
+    for pair in [&mut first, &mut second] {
+        assert!(consume_provider_limit(pair, Some(&limits), 1_000_000, HOUR_BOUNDARY).is_ok());
+        assert!(consume_provider_limit(pair, Some(&limits), 1_000_000, HOUR_BOUNDARY).is_ok());
+        assert!(consume_provider_limit(pair, Some(&limits), 1_000_000, HOUR_BOUNDARY + 1).is_err());
+    }
+
+    // Without published limits only exposure is tracked
+    let mut unlimited = zeroed::<Relationship>();
+    for _ in 0..10 {
+        assert!(consume_provider_limit(&mut unlimited, None, 1_000_000, HOUR_BOUNDARY).is_ok());
+    }
+    assert_eq!(unlimited.open_escrows, 10);
+}
+
+#[test]
+fn dispute_bond_follows_refund_share() {
+    assert_eq!(split_dispute_bond(1_000_000, 100).unwrap(), (1_000_000, 0));
+    assert_eq!(split_dispute_bond(1_000_000, 0).unwrap(), (0, 1_000_000));
+    assert_eq!(split_dispute_bond(1_000_000, 35).unwrap(), (350_000, 650_000));
+    // Rounding favours the API; nothing is lost
+    assert_eq!(split_dispute_bond(999, 50).unwrap(), (499, 500));
+}
+
+#[test]
+fn unresolved_dispute_splits_amount_and_bond() {
+    let config = config();
+    let refund_percentage = config.default_refund_percentage;
+    let (amount, bond) = (10_000_001, 1_000_001);
+
+    let (refund, payment) = split_escrow_amount(amount, refund_percentage).unwrap();
+    let (bond_returned, bond_forfeited) = split_dispute_bond(bond, refund_percentage).unwrap();
+    assert_eq!(refund, amount * refund_percentage as u64 / 100);
+    assert_eq!(bond_returned, bond * refund_percentage as u64 / 100);
+    assert_eq!(refund + payment + bond_returned + bond_forfeited, amount + bond);
+
+    assert_eq!(split_escrow_amount(amount, 0).unwrap(), (0, amount));
+    assert_eq!(split_escrow_amount(amount, 100).unwrap(), (amount, 0));
+}
+
+#[test]
+fn staked_level_follows_configured_threshold() {
+    assert_eq!(config().staked_level_threshold, DEFAULT_STAKED_LEVEL_THRESHOLD);
+
+    let mut limiter = RateLimiter { verification_level: VerificationLevel::Basic, ..zeroed() };
+    apply_stake_level(&mut limiter, 2_000_000_000, 5_000_000_000, 0);
+    assert!(limiter.verification_level == VerificationLevel::Basic);
+
+    apply_stake_level(&mut limiter, 5_000_000_000, 5_000_000_000, 0);
+    assert!(limiter.verification_level == VerificationLevel::Staked);
+
+    apply_stake_level(&mut limiter, 4_999_999_999, 5_000_000_000, 0);
+    assert!(limiter.verification_level == VerificationLevel::Basic);
+}
+
+#[test]
+fn legacy_limiter_starts_with_full_buckets() {
+    // Pre-versioning layout: window counters sit between the level and the bump
+    let entity = Pubkey::new_unique();
+    let mut data = RateLimiter::DISCRIMINATOR.to_vec();
+    (entity, VerificationLevel::Staked, 3u16, 40u16, 1u16, HOUR_BOUNDARY, HOUR_BOUNDARY, 253u8)
+        .serialize(&mut data)
+        .unwrap();
+
+    let (mut limiter, from_version) = upgrade_account::<RateLimiter>(&data).unwrap();
+    assert_eq!(from_version, 0);
+    assert_eq!((limiter.entity, limiter.bump, limiter.version), (entity, 253, RateLimiter::CURRENT_VERSION));
+    assert!(limiter.verification_level == VerificationLevel::Staked);
+    assert_eq!(consume_n(&mut limiter, 11, HOUR_BOUNDARY), 10);
+}
+
+#[test]
+fn session_key_enforces_limits_and_provider_list() {
+    let api = Pubkey::new_unique();
+    let mut session = SessionKey {
+        expires_at: 1_000,
+        max_per_escrow: 100,
+        spend_cap: 250,
+        allowed_providers: [api, Pubkey::default(), Pubkey::default(), Pubkey::default()],
+        ..zeroed()
+    };
+
+    assert!(spend_session(&mut session, 100, Some(&api), 10).is_ok());
+    assert!(spend_session(&mut session, 101, Some(&api), 10).is_err());
+    assert!(spend_session(&mut session, 50, Some(&Pubkey::new_unique()), 10).is_err());
+
+    // Dispute bonds skip the per-escrow and provider checks but count
+    // toward the cap
+    assert!(spend_session(&mut session, 120, None, 10).is_ok());
+    assert!(spend_session(&mut session, 31, Some(&api), 10).is_err());
+    assert_eq!(session.spent, 220);
+
+    assert!(spend_session(&mut session, 1, Some(&api), 1_000).is_err());
+}
+
+#[test]
+fn vault_funding_is_explicit_except_for_session_keys() {
+    assert!(!escrow_funded_from_vault(true, false).unwrap());
+    assert!(escrow_funded_from_vault(true, true).unwrap());
+    assert!(escrow_funded_from_vault(false, true).unwrap());
+    assert!(escrow_funded_from_vault(false, false).is_err());
+}
+
+#[test]
+fn refunds_return_to_the_funding_source() {
+    let (agent_key, vault_key, escrow_key, payout_key) =
+        (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let (amount, bond) = (10_000_000, 1_000_000);
+    let default_refund = config().default_refund_percentage;
+    let sla_refund = sla_refund_percentage(500, 1_500, 9_999);
+
+    // resolve_dispute, settle_sla and settle_unresolved all pay out
+    // through refund_account and distribute_settlement
+    for funded_from_vault in [false, true] {
+        for refund_percentage in [35, sla_refund, default_refund] {
+            let escrow = Escrow { amount, dispute_bond: bond, funded_from_vault, ..zeroed() };
+            let (mut agent_lamports, mut vault_lamports, mut payout_lamports) = (0, 0, 0);
+            let mut escrow_lamports = amount + bond;
+            let (mut agent_data, mut vault_data, mut escrow_data, mut payout_data) = ([], [], [], []);
+            let agent = AccountInfo::new(&agent_key, false, true, &mut agent_lamports, &mut agent_data, &crate::ID, false, 0);
+            let vault = AccountInfo::new(&vault_key, false, true, &mut vault_lamports, &mut vault_data, &crate::ID, false, 0);
+            let escrow_info =
+                AccountInfo::new(&escrow_key, false, true, &mut escrow_lamports, &mut escrow_data, &crate::ID, false, 0);
+            let payout = AccountInfo::new(&payout_key, false, true, &mut payout_lamports, &mut payout_data, &crate::ID, false, 0);
+
+            let refund_to = refund_account(&escrow, agent.clone(), vault.clone());
+            assert_eq!(*refund_to.key, if funded_from_vault { vault_key } else { agent_key });
+
+            let (refund, payment, bond_returned, bond_forfeited) =
+                distribute_settlement(&escrow_info, &escrow, &refund_to, &payout, refund_percentage).unwrap();
+            let (funder, other) = if funded_from_vault { (&vault, &agent) } else { (&agent, &vault) };
+            assert_eq!(funder.lamports(), refund + bond_returned);
+            assert_eq!(other.lamports(), 0);
+            assert_eq!(payout.lamports(), payment + bond_forfeited);
+            assert_eq!(escrow_info.lamports(), 0);
+        }
+    }
+}
+
+#[test]
+fn attested_level_raises_refill_rate_until_expiry() {
+    let mut limiter = RateLimiter { verification_level: VerificationLevel::Basic, ..zeroed() };
+    assert_eq!(consume_n(&mut limiter, 1, HOUR_BOUNDARY), 1);
+
+    // Social refills 50/hour: five tokens in 360 seconds, where Basic
+    // would not have refilled a single one
+    limiter.attested_level = VerificationLevel::Social;
+    limiter.attestation_expires_at = HOUR_BOUNDARY + 3600;
+    assert_eq!(consume_n(&mut limiter, 50, HOUR_BOUNDARY + 360), 5);
+
+    // After expiry the bucket is clamped back to Basic capacity
+    assert_eq!(consume_n(&mut limiter, 50, HOUR_BOUNDARY + 2 * 3600), 1);
+}
+
+#[test]
+fn attestations_cannot_be_replayed() {
+    let mut limiter = RateLimiter { verification_level: VerificationLevel::Basic, ..zeroed() };
+    let expires_at = HOUR_BOUNDARY + 3600;
+    let signed = attestation_message(&limiter, VerificationLevel::KYC, expires_at);
+    assert_eq!(
+        signed,
+        format!("attest:{}:3:{}:0", limiter.entity, expires_at)
+    );
+
+    // Relaying the attestation moves the nonce, as does revoking it
+    bump_attestation_nonce(&mut limiter).unwrap();
+    assert_ne!(attestation_message(&limiter, VerificationLevel::KYC, expires_at), signed);
+    bump_attestation_nonce(&mut limiter).unwrap();
+    assert_eq!(limiter.attestation_nonce, 2);
+}
@@ -0,0 +1,115 @@
+//! Signature, lamport and PDA helpers shared across instructions
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    ed25519_program,
+    sysvar::instructions::load_instruction_at_checked,
+};
+
+use crate::*;
+
+/// Verify Ed25519 signature instruction
+///
+/// Checks that an Ed25519 signature verification instruction exists in the transaction
+/// and validates the signature against the expected message format
+pub fn verify_ed25519_signature(
+    instructions_sysvar: &AccountInfo,
+    signature: &[u8; 64],
+    verifier_pubkey: &Pubkey,
+    message: &[u8],
+) -> Result<()> {
+        // Load the Ed25519 instruction from the sysvar
+        // Expected to be at index 0 (before the current instruction)
+        let ix = load_instruction_at_checked(0, instructions_sysvar)
+            .map_err(|_| error!(EscrowError::InvalidSignature))?;
+
+        // Verify it's the Ed25519 program
+        require!(
+            ix.program_id == ed25519_program::ID,
+            EscrowError::InvalidSignature
+        );
+
+        // Ed25519 instruction data layout:
+        // [0]: num_signatures (should be 1)
+        // [1]: padding
+        // [2..4]: signature_offset (u16)
+        // [4..6]: signature_instruction_index (u16)
+        // [6..8]: public_key_offset (u16)
+        // [8..10]: public_key_instruction_index (u16)
+        // [10..12]: message_data_offset (u16)
+        // [12..14]: message_data_size (u16)
+        // [14..16]: message_instruction_index (u16)
+        // [16..]: data (signature + pubkey + message)
+
+        require!(
+            ix.data.len() >= 16,
+            EscrowError::InvalidSignature
+        );
+
+        // Verify we have exactly 1 signature
+        require!(
+            ix.data[0] == 1,
+            EscrowError::InvalidSignature
+        );
+
+        // Parse offsets
+        let sig_offset = u16::from_le_bytes([ix.data[2], ix.data[3]]) as usize;
+        let pubkey_offset = u16::from_le_bytes([ix.data[6], ix.data[7]]) as usize;
+        let message_offset = u16::from_le_bytes([ix.data[10], ix.data[11]]) as usize;
+        let message_size = u16::from_le_bytes([ix.data[12], ix.data[13]]) as usize;
+
+        // Verify signature matches
+        let ix_signature = &ix.data[sig_offset..sig_offset + 64];
+        require!(
+            ix_signature == signature,
+            EscrowError::InvalidSignature
+        );
+
+        // Verify public key matches
+        let ix_pubkey = &ix.data[pubkey_offset..pubkey_offset + 32];
+        require!(
+            ix_pubkey == verifier_pubkey.as_ref(),
+            EscrowError::InvalidSignature
+        );
+
+        // Verify message matches
+        let ix_message = &ix.data[message_offset..message_offset + message_size];
+        require!(
+            ix_message == message,
+            EscrowError::InvalidSignature
+        );
+
+        Ok(())
+}
+
+/// Move lamports out of a program-owned account
+///
+/// Uses direct lamport manipulation because system transfers require the
+/// source to carry no data.
+pub(crate) fn transfer_lamports(from: &AccountInfo, to: &AccountInfo, amount: u64) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let mut from_lamports = from.try_borrow_mut_lamports()?;
+    let mut to_lamports = to.try_borrow_mut_lamports()?;
+    **from_lamports = from_lamports
+        .checked_sub(amount)
+        .ok_or(EscrowError::ArithmeticOverflow)?;
+    **to_lamports = to_lamports
+        .checked_add(amount)
+        .ok_or(EscrowError::ArithmeticOverflow)?;
+
+    Ok(())
+}
+
+pub(crate) fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub(crate) fn verify_pda(key: &Pubkey, seeds: &[&[u8]]) -> Result<()> {
+    let expected = Pubkey::create_program_address(seeds, &crate::ID)
+        .map_err(|_| error!(ErrorCode::ConstraintSeeds))?;
+    require_keys_eq!(expected, *key, ErrorCode::ConstraintSeeds);
+    Ok(())
+}
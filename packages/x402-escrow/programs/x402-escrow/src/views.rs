@@ -0,0 +1,256 @@
+//! Read-only views for CPI consumers: return types, accounts and handlers
+
+use anchor_lang::prelude::*;
+
+use crate::*;
+
+/// Reputation snapshot returned by `get_reputation`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct ReputationSummary {
+    pub entity: Pubkey,
+    pub entity_type: EntityType,
+    pub total_transactions: u64,
+    pub disputes_filed: u64,
+    pub disputes_won: u64,
+    pub disputes_partial: u64,
+    pub disputes_lost: u64,
+    pub average_quality_received: u8,
+    pub reputation_score: u16,
+    pub last_updated: i64,
+}
+
+/// Escrow snapshot returned by `get_escrow_status`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct EscrowSummary {
+    pub escrow: Pubkey,
+    pub agent: Pubkey,
+    pub api: Pubkey,
+    pub amount: u64,
+    pub status: EscrowStatus,
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub quality_score: Option<u8>,
+    pub refund_percentage: Option<u8>,
+}
+
+/// Agreed escrow scope returned by `get_work_agreement`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct WorkAgreementSummary {
+    pub escrow: Pubkey,
+    pub agreement_hash: [u8; 32],         // Committed to by verifier messages
+    pub query: String,
+    pub required_fields: u8,
+    pub min_records: u32,
+    pub max_age_days: u32,
+    pub min_quality_score: u8,
+}
+
+/// Agent/API history returned by `get_relationship`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct RelationshipSummary {
+    pub agent: Pubkey,
+    pub api: Pubkey,
+    pub total_transactions: u64,
+    pub total_volume: u64,
+    pub disputes: u64,
+    pub average_quality: Option<u8>,      // None until a dispute settles
+    pub first_transaction_at: i64,
+    pub last_transaction_at: i64,
+}
+
+/// Quality score distribution returned by `get_quality_distribution`
+///
+/// Percentiles are reported as the lower edge of their decile (0, 10, ... 90).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct QualityDistribution {
+    pub entity: Pubkey,
+    pub histogram: [u32; QUALITY_BUCKETS],
+    pub samples: u64,
+    pub mean: Option<u8>,                 // None until a score is recorded
+    pub p10: Option<u8>,
+    pub p50: Option<u8>,
+    pub p90: Option<u8>,
+}
+
+/// Dispute pricing returned by `quote_dispute_cost`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct DisputeQuote {
+    pub entity: Pubkey,
+    pub dispute_cost: u64,                // lamports
+    pub base_cost: u64,                   // lamports
+    pub multiplier: u64,                  // dispute_cost / base_cost
+}
+
+// View accounts are decoded with `Versioned::load_any_version` so that
+// readers keep working on accounts that have not been migrated yet.
+#[derive(Accounts)]
+pub struct GetReputation<'info> {
+    /// CHECK: Decoded and PDA-checked in the handler
+    #[account(owner = crate::ID)]
+    pub reputation: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GetEscrowStatus<'info> {
+    /// CHECK: Decoded and PDA-checked in the handler
+    #[account(owner = crate::ID)]
+    pub escrow: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct QuoteDisputeCost<'info> {
+    /// CHECK: Decoded and PDA-checked in the handler
+    #[account(owner = crate::ID)]
+    pub reputation: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GetRelationship<'info> {
+    /// CHECK: Decoded and PDA-checked in the handler
+    #[account(owner = crate::ID)]
+    pub relationship: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GetWorkAgreement<'info> {
+    /// CHECK: Decoded and PDA-checked in the handler
+    #[account(owner = crate::ID)]
+    pub agreement: AccountInfo<'info>,
+}
+
+pub(crate) fn get_reputation(ctx: Context<GetReputation>) -> Result<ReputationSummary> {
+    let reputation = EntityReputation::load_any_version(&ctx.accounts.reputation)?;
+    verify_pda(
+        ctx.accounts.reputation.key,
+        &[b"reputation", reputation.entity.as_ref(), &[reputation.bump]],
+    )?;
+
+    Ok(ReputationSummary {
+        entity: reputation.entity,
+        entity_type: reputation.entity_type.clone(),
+        total_transactions: reputation.total_transactions,
+        disputes_filed: reputation.disputes_filed,
+        disputes_won: reputation.disputes_won,
+        disputes_partial: reputation.disputes_partial,
+        disputes_lost: reputation.disputes_lost,
+        average_quality_received: reputation.average_quality_received,
+        reputation_score: reputation.reputation_score,
+        last_updated: reputation.last_updated,
+    })
+}
+
+
+pub(crate) fn get_escrow_status(ctx: Context<GetEscrowStatus>) -> Result<EscrowSummary> {
+    let escrow = Escrow::load_any_version(&ctx.accounts.escrow)?;
+    verify_pda(
+        ctx.accounts.escrow.key,
+        &[b"escrow", escrow.transaction_id.as_bytes(), &[escrow.bump]],
+    )?;
+
+    Ok(EscrowSummary {
+        escrow: ctx.accounts.escrow.key(),
+        agent: escrow.agent,
+        api: escrow.api,
+        amount: escrow.amount,
+        status: escrow.status,
+        created_at: escrow.created_at,
+        expires_at: escrow.expires_at,
+        quality_score: escrow.quality_score,
+        refund_percentage: escrow.refund_percentage,
+    })
+}
+
+
+pub(crate) fn quote_dispute_cost(ctx: Context<QuoteDisputeCost>) -> Result<DisputeQuote> {
+    let reputation = EntityReputation::load_any_version(&ctx.accounts.reputation)?;
+    verify_pda(
+        ctx.accounts.reputation.key,
+        &[b"reputation", reputation.entity.as_ref(), &[reputation.bump]],
+    )?;
+    let config = quote_config(ctx.remaining_accounts)?;
+    let dispute_cost = calculate_dispute_cost(&reputation, &config, Clock::get()?.unix_timestamp);
+
+    Ok(DisputeQuote {
+        entity: reputation.entity,
+        dispute_cost,
+        base_cost: config.base_dispute_cost,
+        multiplier: dispute_cost / config.base_dispute_cost.max(1),
+    })
+}
+
+
+pub(crate) fn get_relationship(ctx: Context<GetRelationship>) -> Result<RelationshipSummary> {
+    let relationship = Relationship::load_any_version(&ctx.accounts.relationship)?;
+    verify_pda(
+        ctx.accounts.relationship.key,
+        &[
+            b"relationship",
+            relationship.agent.as_ref(),
+            relationship.api.as_ref(),
+            &[relationship.bump],
+        ],
+    )?;
+
+    Ok(RelationshipSummary {
+        agent: relationship.agent,
+        api: relationship.api,
+        total_transactions: relationship.total_transactions,
+        total_volume: relationship.total_volume,
+        disputes: relationship.disputes,
+        average_quality: relationship_average_quality(&relationship),
+        first_transaction_at: relationship.first_transaction_at,
+        last_transaction_at: relationship.last_transaction_at,
+    })
+}
+
+
+pub(crate) fn get_quality_distribution(ctx: Context<GetReputation>) -> Result<QualityDistribution> {
+    let reputation = EntityReputation::load_any_version(&ctx.accounts.reputation)?;
+    verify_pda(
+        ctx.accounts.reputation.key,
+        &[b"reputation", reputation.entity.as_ref(), &[reputation.bump]],
+    )?;
+
+    Ok(QualityDistribution {
+        entity: reputation.entity,
+        histogram: reputation.quality_histogram,
+        samples: reputation.quality_samples,
+        mean: quality_mean(&reputation),
+        p10: quality_percentile(&reputation, 10),
+        p50: quality_percentile(&reputation, 50),
+        p90: quality_percentile(&reputation, 90),
+    })
+}
+
+
+pub(crate) fn get_work_agreement(ctx: Context<GetWorkAgreement>) -> Result<WorkAgreementSummary> {
+    let agreement = WorkAgreement::load_any_version(&ctx.accounts.agreement)?;
+    verify_pda(
+        ctx.accounts.agreement.key,
+        &[b"agreement", agreement.escrow.as_ref(), &[agreement.bump]],
+    )?;
+
+    Ok(WorkAgreementSummary {
+        escrow: agreement.escrow,
+        agreement_hash: work_agreement_hash(&agreement),
+        query: agreement.query.clone(),
+        required_fields: agreement.required_fields,
+        min_records: agreement.min_records,
+        max_age_days: agreement.max_age_days,
+        min_quality_score: agreement.min_quality_score,
+    })
+}
+
+
+/// Config for `quote_dispute_cost`, passed as an optional trailing account
+///
+/// Callers built before the config existed pass only the reputation and are
+/// quoted the default schedule.
+pub(crate) fn quote_config(remaining_accounts: &[AccountInfo]) -> Result<ProgramConfig> {
+    let Some(info) = remaining_accounts.first() else {
+        return Ok(ProgramConfig::new(Pubkey::default(), 0));
+    };
+    let config = ProgramConfig::load_any_version(info)?;
+    verify_pda(info.key, &[b"config", &[config.bump]])?;
+    Ok(config)
+}
@@ -2,7 +2,14 @@ import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { X402Escrow } from "../target/types/x402_escrow";
 import { expect } from "chai";
-import { PublicKey, SystemProgram, Keypair, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import {
+  PublicKey,
+  SystemProgram,
+  Keypair,
+  LAMPORTS_PER_SOL,
+  Ed25519Program,
+  BPF_LOADER_UPGRADEABLE_PROGRAM_ID,
+} from "@solana/web3.js";
 
 describe("x402-escrow", () => {
   const provider = anchor.AnchorProvider.env();
@@ -17,9 +24,108 @@ describe("x402-escrow", () => {
   const ESCROW_AMOUNT = 0.01 * LAMPORTS_PER_SOL;
   const TIME_LOCK = 86400;
 
-  beforeEach(() => {
+  async function initReputation(entity: PublicKey) {
+    await program.methods
+      .initReputation()
+      .accounts({
+        entity,
+        payer: agent.publicKey,
+      })
+      .rpc();
+  }
+
+  // The suite opens more escrows per hour than Basic allows, so the agent
+  // is attested at KYC by an attestor registered in the program config
+  async function attestAgent() {
+    const [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    if (!(await program.account.programConfig.fetchNullable(configPda))) {
+      const [programData] = PublicKey.findProgramAddressSync(
+        [program.programId.toBuffer()],
+        BPF_LOADER_UPGRADEABLE_PROGRAM_ID
+      );
+      await program.methods
+        .initConfig()
+        .accounts({
+          authority: agent.publicKey,
+          program: program.programId,
+          programData,
+        })
+        .rpc();
+    }
+
+    const attestor = Keypair.generate();
+    const [attestorRecord] = PublicKey.findProgramAddressSync(
+      [Buffer.from("attestor"), attestor.publicKey.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .addAttestor({ kyc: {} })
+      .accounts({
+        attestor: attestor.publicKey,
+        authority: agent.publicKey,
+      })
+      .rpc();
+
+    const [rateLimiter] = PublicKey.findProgramAddressSync(
+      [Buffer.from("rate_limit"), agent.publicKey.toBuffer()],
+      program.programId
+    );
+    const { attestationNonce } = await program.account.rateLimiter.fetch(rateLimiter);
+    const expiresAt = Math.floor(Date.now() / 1000) + 86400;
+    const message = Buffer.from(
+      `attest:${agent.publicKey.toBase58()}:3:${expiresAt}:${attestationNonce.toString()}`
+    );
+    const ed25519Ix = Ed25519Program.createInstructionWithPrivateKey({
+      privateKey: attestor.secretKey,
+      message,
+    });
+    // Signature sits after the 16-byte header and 32-byte public key
+    const signature = Array.from(ed25519Ix.data.subarray(48, 112));
+
+    await program.methods
+      .attestVerification({ kyc: {} }, new anchor.BN(expiresAt), signature)
+      .accounts({
+        rateLimiter,
+        attestorRecord,
+      })
+      .preInstructions([ed25519Ix])
+      .rpc();
+  }
+
+  before(async () => {
+    await initReputation(agent.publicKey);
+    await program.methods
+      .initRateLimiter()
+      .accounts({
+        entity: agent.publicKey,
+        payer: agent.publicKey,
+      })
+      .rpc();
+    await attestAgent();
+  });
+
+  beforeEach(async () => {
     api = Keypair.generate();
     verifier = Keypair.generate();
+
+    // The provider lists no verifiers, so disputes fall back to the config's
+    await program.methods
+      .setDefaultVerifiers([verifier.publicKey])
+      .accounts({ authority: agent.publicKey })
+      .rpc();
+
+    await initReputation(api.publicKey);
+    await program.methods
+      .initRelationship()
+      .accounts({
+        agent: agent.publicKey,
+        api: api.publicKey,
+        payer: agent.publicKey,
+      })
+      .rpc();
   });
 
   describe("initialize_escrow", () => {
@@ -34,11 +140,14 @@ describe("x402-escrow", () => {
         .initializeEscrow(
           new anchor.BN(ESCROW_AMOUNT),
           new anchor.BN(TIME_LOCK),
-          transactionId
+          transactionId,
+          null, // metadata
+          false // fund_from_vault
         )
         .accounts({
           escrow: escrowPda,
           agent: agent.publicKey,
+          authority: agent.publicKey,
           api: api.publicKey,
           systemProgram: SystemProgram.programId,
         })
@@ -66,11 +175,14 @@ describe("x402-escrow", () => {
         .initializeEscrow(
           new anchor.BN(ESCROW_AMOUNT),
           new anchor.BN(TIME_LOCK),
-          transactionId
+          transactionId,
+          null, // metadata
+          false // fund_from_vault
         )
         .accounts({
           escrow: escrowPda,
           agent: agent.publicKey,
+          authority: agent.publicKey,
           api: api.publicKey,
           systemProgram: SystemProgram.programId,
         })
@@ -98,11 +210,14 @@ describe("x402-escrow", () => {
         .initializeEscrow(
           new anchor.BN(ESCROW_AMOUNT),
           new anchor.BN(TIME_LOCK),
-          transactionId
+          transactionId,
+          null, // metadata
+          false // fund_from_vault
         )
         .accounts({
           escrow: escrowPda,
           agent: agent.publicKey,
+          authority: agent.publicKey,
           api: api.publicKey,
           systemProgram: SystemProgram.programId,
         })
@@ -115,6 +230,7 @@ describe("x402-escrow", () => {
         .accounts({
           escrow: escrowPda,
           agent: agent.publicKey,
+          authority: agent.publicKey,
         })
         .rpc();
 
@@ -131,6 +247,7 @@ describe("x402-escrow", () => {
           .accounts({
             escrow: escrowPda,
             agent: unauthorizedAgent.publicKey,
+            authority: unauthorizedAgent.publicKey,
           })
           .signers([unauthorizedAgent])
           .rpc();
@@ -157,11 +274,14 @@ describe("x402-escrow", () => {
         .initializeEscrow(
           new anchor.BN(ESCROW_AMOUNT),
           new anchor.BN(TIME_LOCK),
-          transactionId
+          transactionId,
+          null, // metadata
+          false // fund_from_vault
         )
         .accounts({
           escrow: escrowPda,
           agent: agent.publicKey,
+          authority: agent.publicKey,
           api: api.publicKey,
           systemProgram: SystemProgram.programId,
         })
@@ -172,6 +292,7 @@ describe("x402-escrow", () => {
         .accounts({
           escrow: escrowPda,
           agent: agent.publicKey,
+          authority: agent.publicKey,
         })
         .rpc();
     });
@@ -411,11 +532,14 @@ describe("x402-escrow", () => {
         .initializeEscrow(
           new anchor.BN(ESCROW_AMOUNT),
           new anchor.BN(TIME_LOCK),
-          transactionId
+          transactionId,
+          null, // metadata
+          false // fund_from_vault
         )
         .accounts({
           escrow: escrowPda,
           agent: agent.publicKey,
+          authority: agent.publicKey,
           api: api.publicKey,
           systemProgram: SystemProgram.programId,
         })
@@ -463,4 +587,40 @@ describe("x402-escrow", () => {
       expect(apiGain).to.be.greaterThan(ESCROW_AMOUNT * 0.99);
     });
   });
+
+  describe("view instructions", () => {
+    it("returns escrow status via return data", async () => {
+      const transactionId = `tx_${Date.now()}`;
+      const [escrowPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow"), Buffer.from(transactionId)],
+        program.programId
+      );
+
+      await program.methods
+        .initializeEscrow(
+          new anchor.BN(ESCROW_AMOUNT),
+          new anchor.BN(TIME_LOCK),
+          transactionId,
+          null, // metadata
+          false // fund_from_vault
+        )
+        .accounts({
+          escrow: escrowPda,
+          agent: agent.publicKey,
+          authority: agent.publicKey,
+          api: api.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const summary = await program.methods
+        .getEscrowStatus()
+        .accounts({ escrow: escrowPda })
+        .view();
+
+      expect(summary.escrow.toBase58()).to.equal(escrowPda.toBase58());
+      expect(summary.amount.toNumber()).to.equal(ESCROW_AMOUNT);
+      expect(summary.status).to.deep.equal({ active: {} });
+    });
+  });
 });
@@ -32,6 +32,31 @@ describe('x402Resolve Integration Tests', () => {
   const MIN_AMOUNT = 0.001 * LAMPORTS_PER_SOL;
   const MAX_AMOUNT = 1000 * LAMPORTS_PER_SOL;
 
+  // Disputes read the program config, which the upgrade authority (the
+  // provider wallet under `anchor test`) creates once after deploy
+  before(async () => {
+    const [configPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from('config')],
+      program.programId
+    );
+    if (await program.account.programConfig.fetchNullable(configPda)) {
+      return;
+    }
+
+    const [programData] = anchor.web3.PublicKey.findProgramAddressSync(
+      [program.programId.toBuffer()],
+      anchor.web3.BPF_LOADER_UPGRADEABLE_PROGRAM_ID
+    );
+    await program.methods
+      .initConfig()
+      .accounts({
+        authority: provider.wallet.publicKey,
+        program: program.programId,
+        programData,
+      })
+      .rpc();
+  });
+
   beforeEach(async () => {
     // Create fresh wallets for each test
     agent = Keypair.generate();
@@ -41,6 +66,25 @@ describe('x402Resolve Integration Tests', () => {
     // Airdrop SOL for testing
     await connection.requestAirdrop(agent.publicKey, 2 * LAMPORTS_PER_SOL);
     await new Promise(resolve => setTimeout(resolve, 1000)); // Wait for confirmation
+
+    // Both parties need reputation accounts before an escrow can be opened
+    for (const entity of [agent.publicKey, api.publicKey]) {
+      await program.methods
+        .initReputation()
+        .accounts({ entity, payer: agent.publicKey })
+        .signers([agent])
+        .rpc();
+    }
+    await program.methods
+      .initRateLimiter()
+      .accounts({ entity: agent.publicKey, payer: agent.publicKey })
+      .signers([agent])
+      .rpc();
+    await program.methods
+      .initRelationship()
+      .accounts({ agent: agent.publicKey, api: api.publicKey, payer: agent.publicKey })
+      .signers([agent])
+      .rpc();
   });
 
   function deriveEscrowPDA(transactionId: string): [anchor.web3.PublicKey, number] {
@@ -59,11 +103,14 @@ describe('x402Resolve Integration Tests', () => {
         .initializeEscrow(
           new anchor.BN(ESCROW_AMOUNT),
           new anchor.BN(TIME_LOCK),
-          transactionId
+          transactionId,
+          null, // metadata
+          false // fund_from_vault
         )
         .accounts({
           escrow: escrowPda,
           agent: agent.publicKey,
+          authority: agent.publicKey,
           api: api.publicKey,
           systemProgram: anchor.web3.SystemProgram.programId,
         })
@@ -91,11 +138,14 @@ describe('x402Resolve Integration Tests', () => {
           .initializeEscrow(
             new anchor.BN(tooSmall),
             new anchor.BN(TIME_LOCK),
-            transactionId
+            transactionId,
+            null, // metadata
+            false // fund_from_vault
           )
           .accounts({
             escrow: escrowPda,
             agent: agent.publicKey,
+            authority: agent.publicKey,
             api: api.publicKey,
             systemProgram: anchor.web3.SystemProgram.programId,
           })
@@ -118,11 +168,14 @@ describe('x402Resolve Integration Tests', () => {
           .initializeEscrow(
             new anchor.BN(tooLarge),
             new anchor.BN(TIME_LOCK),
-            transactionId
+            transactionId,
+            null, // metadata
+            false // fund_from_vault
           )
           .accounts({
             escrow: escrowPda,
             agent: agent.publicKey,
+            authority: agent.publicKey,
             api: api.publicKey,
             systemProgram: anchor.web3.SystemProgram.programId,
           })
@@ -145,11 +198,14 @@ describe('x402Resolve Integration Tests', () => {
           .initializeEscrow(
             new anchor.BN(ESCROW_AMOUNT),
             new anchor.BN(invalidTimeLock),
-            transactionId
+            transactionId,
+            null, // metadata
+            false // fund_from_vault
           )
           .accounts({
             escrow: escrowPda,
             agent: agent.publicKey,
+            authority: agent.publicKey,
             api: api.publicKey,
             systemProgram: anchor.web3.SystemProgram.programId,
           })
@@ -176,11 +232,14 @@ describe('x402Resolve Integration Tests', () => {
         .initializeEscrow(
           new anchor.BN(ESCROW_AMOUNT),
           new anchor.BN(TIME_LOCK),
-          transactionId
+          transactionId,
+          null, // metadata
+          false // fund_from_vault
         )
         .accounts({
           escrow: escrowPda,
           agent: agent.publicKey,
+          authority: agent.publicKey,
           api: api.publicKey,
           systemProgram: anchor.web3.SystemProgram.programId,
         })
@@ -194,6 +253,7 @@ describe('x402Resolve Integration Tests', () => {
         .accounts({
           escrow: escrowPda,
           agent: agent.publicKey,
+          authority: agent.publicKey,
         })
         .signers([agent])
         .rpc();
@@ -213,6 +273,7 @@ describe('x402Resolve Integration Tests', () => {
           .accounts({
             escrow: escrowPda,
             agent: nonAgent.publicKey,
+            authority: nonAgent.publicKey,
           })
           .signers([nonAgent])
           .rpc();
@@ -236,11 +297,14 @@ describe('x402Resolve Integration Tests', () => {
         .initializeEscrow(
           new anchor.BN(ESCROW_AMOUNT),
           new anchor.BN(TIME_LOCK),
-          transactionId
+          transactionId,
+          null, // metadata
+          false // fund_from_vault
         )
         .accounts({
           escrow: escrowPda,
           agent: agent.publicKey,
+          authority: agent.publicKey,
           api: api.publicKey,
           systemProgram: anchor.web3.SystemProgram.programId,
         })